@@ -1,8 +1,8 @@
-use super::official;
+use super::{official, to_path};
 /// provide related function with minecraft fabric meta api
 /// https://github.com/FabricMC/fabric-meta
 use serde::{Deserialize, Serialize};
-use std::{borrow::Cow, collections::VecDeque};
+use std::borrow::Cow;
 
 /// Lists all of the supported game versions.
 #[derive(Debug, Serialize, Deserialize)]
@@ -21,7 +21,7 @@ impl Game {
     /// ```
     pub fn fetch(mirror: &str) -> anyhow::Result<Vec<Self>> {
         let url = mirror.to_owned() + "/v2/versions/game";
-        let client = reqwest::blocking::Client::new();
+        let client = super::http_client();
         fetch!(client, url, json)
     }
 }
@@ -48,7 +48,7 @@ impl Yarn {
     /// ```
     pub fn fetch(mirror: &str) -> anyhow::Result<Vec<Self>> {
         let url = mirror.to_owned() + "/v2/versions/yarn";
-        let client = reqwest::blocking::Client::new();
+        let client = super::http_client();
         fetch!(client, url, json)
     }
 }
@@ -73,7 +73,7 @@ impl Loader {
     /// ```
     pub fn fetch(mirror: &str) -> anyhow::Result<Vec<Self>> {
         let url = mirror.to_owned() + "/v2/versions/loader";
-        let client = reqwest::blocking::Client::new();
+        let client = super::http_client();
         fetch!(client, url, json)
     }
 }
@@ -96,7 +96,7 @@ impl Intermediary {
     /// ```
     pub fn fetch(mirror: &str) -> anyhow::Result<Vec<Self>> {
         let url = mirror.to_owned() + "/v2/versions/intermediary";
-        let client = reqwest::blocking::Client::new();
+        let client = super::http_client();
         fetch!(client, url, json)
     }
 }
@@ -135,7 +135,7 @@ impl Versions {
     /// ```
     pub fn fetch(mirror: &str) -> anyhow::Result<Self> {
         let url = mirror.to_owned() + "/v2/versions";
-        let client = reqwest::blocking::Client::new();
+        let client = super::http_client();
         fetch!(client, url, json)
     }
 }
@@ -160,43 +160,35 @@ pub struct Library {
 
 impl From<Library> for official::Library {
     fn from(lib: Library) -> Self {
+        let path = to_path(&lib.name);
+        // fabric only gives the maven repository, keep the full url like the
+        // official libraries
         let artifact = official::Artifact {
-            path: to_path(lib.name.clone()),
+            url: lib.url + &path,
+            path,
             sha1: lib.sha1,
             size: lib.size,
-            url: lib.url,
         };
         let downloads = official::LibDownloads {
-            artifact,
+            artifact: Some(artifact),
             classifiers: None,
         };
         official::Library {
-            downloads,
+            downloads: Some(downloads),
             name: lib.name,
             natives: None,
             rules: None,
+            extract: None,
         }
     }
 }
 
-fn to_path(name: String) -> String {
-    let mut name: VecDeque<&str> = name.split(':').collect();
-    let version = &name.pop_back().unwrap();
-    let file = &name.pop_back().unwrap();
-    let mut res = "".to_owned();
-    for i in name {
-        res += i.replace('.', "/").as_ref();
-        res += "/";
-    }
-    format!("{res}{file}/{version}/{file}-{version}.jar")
-}
-
 #[test]
 fn test_name_to_path() {
     let name = "net.fabricmc:sponge-mixin:0.13.3+mixin.0.8.5".to_owned();
     let ans = "net/fabricmc/sponge-mixin/0.13.3+mixin.0.8.5/sponge-mixin-0.13.3+mixin.0.8.5.jar"
         .to_owned();
-    assert_eq!(to_path(name), ans);
+    assert_eq!(to_path(&name), ans);
 }
 
 /// return the JSON file that should be used in the standard Minecraft launcher.
@@ -240,7 +232,7 @@ impl Profile {
             + "/"
             + loader_version.replace(' ', "%20").as_ref()
             + "/profile/json";
-        let client = reqwest::blocking::Client::new();
+        let client = super::http_client();
         fetch!(client, url, json)
     }
 }
@@ -0,0 +1,342 @@
+use super::{official, to_path};
+/// provide related function with minecraft forge maven and installer
+/// https://files.minecraftforge.net
+use serde::{Deserialize, Serialize};
+use sha1::{Digest, Sha1};
+use std::{
+    collections::HashMap,
+    fs,
+    io::{Cursor, Read},
+    path::Path,
+};
+
+const PROMOTIONS_URL: &str =
+    "https://files.minecraftforge.net/net/minecraftforge/forge/promotions_slim.json";
+
+/// recommended and latest forge build of every game version
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Promotions {
+    pub homepage: String,
+    pub promos: HashMap<String, String>,
+}
+
+impl Promotions {
+    /// fetch forge promotions
+    /// # Examples
+    /// ```
+    /// use launcher::api::forge::Promotions;
+    /// let _ = Promotions::fetch().unwrap();
+    /// ```
+    pub fn fetch() -> anyhow::Result<Self> {
+        let url = PROMOTIONS_URL.to_owned();
+        let client = super::http_client();
+        fetch!(client, url, json)
+    }
+
+    /// return the recommended forge build of `game_version`, or the latest
+    /// build if no build is recommended
+    /// # Examples
+    /// ```
+    /// use launcher::api::forge::Promotions;
+    /// let promotions = Promotions::fetch().unwrap();
+    /// assert!(promotions.recommended("1.12.2").is_some());
+    /// ```
+    pub fn recommended(&self, game_version: &str) -> Option<String> {
+        self.promos
+            .get(&format!("{game_version}-recommended"))
+            .or_else(|| self.latest_ref(game_version))
+            .cloned()
+    }
+
+    /// return the latest forge build of `game_version`
+    pub fn latest(&self, game_version: &str) -> Option<String> {
+        self.latest_ref(game_version).cloned()
+    }
+
+    fn latest_ref(&self, game_version: &str) -> Option<&String> {
+        self.promos.get(&format!("{game_version}-latest"))
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Arguments {
+    #[serde(default)]
+    pub game: Vec<serde_json::Value>,
+    #[serde(default)]
+    pub jvm: Vec<serde_json::Value>,
+}
+
+/// library of a forge profile, either in the official format or only with a
+/// maven name and repository url
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Library {
+    name: String,
+    url: Option<String>,
+    downloads: Option<official::LibDownloads>,
+    checksums: Option<Vec<String>>,
+    clientreq: Option<bool>,
+}
+
+impl From<Library> for official::Library {
+    fn from(lib: Library) -> Self {
+        let downloads = lib.downloads.unwrap_or_else(|| {
+            let path = to_path(&lib.name);
+            let repository = lib
+                .url
+                .unwrap_or_else(|| "https://libraries.minecraft.net/".into());
+            let artifact = official::Artifact {
+                url: repository + &path,
+                path,
+                sha1: lib.checksums.and_then(|x| x.first().cloned()),
+                size: None,
+            };
+            official::LibDownloads {
+                artifact: Some(artifact),
+                classifiers: None,
+            }
+        });
+        official::Library {
+            downloads: Some(downloads),
+            name: lib.name,
+            natives: None,
+            rules: None,
+            extract: None,
+        }
+    }
+}
+
+/// version profile that forge installs next to the official version json
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Profile {
+    id: String,
+    #[serde(rename = "mainClass")]
+    main_class: String,
+    #[serde(rename = "minecraftArguments")]
+    minecraft_arguments: Option<String>,
+    arguments: Option<Arguments>,
+    libraries: Vec<Library>,
+}
+
+#[derive(Debug, Deserialize)]
+struct LegacyInstall {
+    path: String,
+    #[serde(rename = "filePath")]
+    file_path: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct Processor {
+    #[serde(default)]
+    sides: Vec<String>,
+}
+
+/// install_profile.json of the forge installer
+/// forge before 1.13 embeds the profile as `versionInfo`, later versions ship
+/// it as version.json and patch the client with `processors`
+#[derive(Debug, Deserialize)]
+struct InstallProfile {
+    install: Option<LegacyInstall>,
+    #[serde(rename = "versionInfo")]
+    version_info: Option<Profile>,
+    #[serde(default)]
+    processors: Vec<Processor>,
+}
+
+/// forge installer jar
+pub struct Installer {
+    name: String,
+    archive: zip::ZipArchive<Cursor<bytes::Bytes>>,
+}
+
+impl Installer {
+    /// fetch the installer of `forge_version` for `game_version` from a forge
+    /// maven mirror
+    /// # Examples
+    /// ```
+    /// use launcher::api::forge::Installer;
+    /// let mirror = "https://bmclapi2.bangbang93.com/maven/";
+    /// let _ = Installer::fetch(mirror, "1.12.2", "14.23.5.2859").unwrap();
+    /// ```
+    pub fn fetch(mirror: &str, game_version: &str, forge_version: &str) -> anyhow::Result<Self> {
+        let name = format!("{game_version}-{forge_version}");
+        let url = format!("{mirror}net/minecraftforge/forge/{name}/forge-{name}-installer.jar");
+        Self::fetch_url(format!("forge {name}"), url)
+    }
+
+    /// fetch the installer `name` at `url`, for loaders which reuse the
+    /// format of the forge installer
+    pub(crate) fn fetch_url(name: String, url: String) -> anyhow::Result<Self> {
+        let client = super::http_client();
+        let data = fetch!(client, url, bytes)?;
+        Ok(Installer {
+            name,
+            archive: zip::ZipArchive::new(Cursor::new(data))?,
+        })
+    }
+
+    /// read the version profile from the installer, and extract the
+    /// libraries bundled in the installer into `libraries_dir`
+    /// # Error
+    /// Return Error if the installer needs processors to patch the client,
+    /// which is not supported
+    pub fn install<P>(&mut self, libraries_dir: &P) -> anyhow::Result<Profile>
+    where
+        P: AsRef<Path>,
+    {
+        let install_profile = self.install_profile()?;
+        if let (Some(install), Some(mut profile)) =
+            (install_profile.install, install_profile.version_info)
+        {
+            // the universal jar is only shipped inside the installer
+            let data = self.read(&install.file_path)?;
+            let path = to_path(&install.path);
+            let file = libraries_dir.as_ref().join(&path);
+            fs::create_dir_all(file.parent().unwrap())?;
+            fs::write(&file, &data)?;
+            for lib in profile
+                .libraries
+                .iter_mut()
+                .filter(|x| x.name == install.path)
+            {
+                lib.checksums = Some(vec![hex::encode(Sha1::digest(&data))]);
+            }
+            return Ok(profile);
+        }
+
+        let profile: Profile = serde_json::from_slice(&self.read("version.json")?)?;
+        for lib in &profile.libraries {
+            let Some(artifact) = lib.downloads.as_ref().and_then(|x| x.artifact.as_ref()) else {
+                continue;
+            };
+            // libraries without url are only shipped inside the installer
+            if artifact.url.is_empty() {
+                let data = self.read(&format!("maven/{}", artifact.path))?;
+                let file = libraries_dir.as_ref().join(&artifact.path);
+                fs::create_dir_all(file.parent().unwrap())?;
+                fs::write(file, data)?;
+            }
+        }
+        Ok(profile)
+    }
+
+    /// read the version profile from the installer without extracting the
+    /// bundled libraries
+    /// # Error
+    /// Return Error if the installer needs processors to patch the client
+    pub fn profile(&mut self) -> anyhow::Result<Profile> {
+        match self.install_profile()?.version_info {
+            Some(profile) => Ok(profile),
+            None => Ok(serde_json::from_slice(&self.read("version.json")?)?),
+        }
+    }
+
+    fn install_profile(&mut self) -> anyhow::Result<InstallProfile> {
+        let install_profile: InstallProfile =
+            serde_json::from_slice(&self.read("install_profile.json")?)?;
+        if install_profile
+            .processors
+            .iter()
+            .any(|x| x.sides.is_empty() || x.sides.iter().any(|side| side == "client"))
+        {
+            anyhow::bail!(
+                "{} needs the installer processors to patch the client, which is not supported, \
+                 please install it with the official installer",
+                self.name
+            );
+        }
+        Ok(install_profile)
+    }
+
+    fn read(&mut self, name: &str) -> anyhow::Result<Vec<u8>> {
+        let mut file = self
+            .archive
+            .by_name(name)
+            .map_err(|_| anyhow::anyhow!("{} has no {name}", self.name))?;
+        let mut data = vec![];
+        file.read_to_end(&mut data)?;
+        Ok(data)
+    }
+}
+
+impl official::MergeVersion for Profile {
+    fn official_libraries(&self) -> Option<Vec<official::Library>> {
+        Some(
+            self.libraries
+                .iter()
+                .filter(|x| x.clientreq != Some(false))
+                .map(|x| x.clone().into())
+                .collect(),
+        )
+    }
+    fn main_class(&self) -> Option<String> {
+        Some(self.main_class.clone())
+    }
+    fn arguments_game(&self) -> Option<Vec<serde_json::Value>> {
+        self.arguments.as_ref().map(|x| x.game.clone())
+    }
+    fn arguments_jvm(&self) -> Option<Vec<serde_json::Value>> {
+        self.arguments.as_ref().map(|x| x.jvm.clone())
+    }
+    fn minecraft_arguments(&self) -> Option<String> {
+        self.minecraft_arguments.clone()
+    }
+}
+
+#[cfg(test)]
+fn zip_installer(files: &[(&str, &str)]) -> Installer {
+    use std::io::Write;
+    let mut writer = zip::ZipWriter::new(Cursor::new(vec![]));
+    for (name, content) in files {
+        writer
+            .start_file(*name, zip::write::SimpleFileOptions::default())
+            .unwrap();
+        writer.write_all(content.as_bytes()).unwrap();
+    }
+    let data = writer.finish().unwrap().into_inner();
+    Installer {
+        name: "forge test".into(),
+        archive: zip::ZipArchive::new(Cursor::new(data.into())).unwrap(),
+    }
+}
+
+#[test]
+fn test_legacy_installer() {
+    let install_profile = r#"{
+        "install": {"path": "net.minecraftforge:forge:1.12.2-1", "filePath": "forge.jar"},
+        "versionInfo": {
+            "id": "1.12.2-forge-1",
+            "mainClass": "net.minecraft.launchwrapper.Launch",
+            "minecraftArguments": "--tweakClass net.minecraftforge.fml.common.launcher.FMLTweaker",
+            "libraries": [
+                {"name": "net.minecraftforge:forge:1.12.2-1", "url": "https://maven.minecraftforge.net/"},
+                {"name": "net.minecraft:launchwrapper:1.12"}
+            ]
+        }
+    }"#;
+    let mut installer = zip_installer(&[
+        ("install_profile.json", install_profile),
+        ("forge.jar", "universal"),
+    ]);
+    let dir = crate::TestDir::new("legacy_installer");
+    let profile = installer.install(&dir).unwrap();
+    let jar = dir.join("net/minecraftforge/forge/1.12.2-1/forge-1.12.2-1.jar");
+    assert_eq!(fs::read_to_string(jar).unwrap(), "universal");
+
+    let libs = official::MergeVersion::official_libraries(&profile).unwrap();
+    let forge = libs[0].artifact().unwrap();
+    assert_eq!(forge.sha1, Some(hex::encode(Sha1::digest("universal"))));
+    let launchwrapper = libs[1].artifact().unwrap();
+    assert_eq!(
+        launchwrapper.url,
+        "https://libraries.minecraft.net/net/minecraft/launchwrapper/1.12/launchwrapper-1.12.jar"
+    );
+}
+
+#[test]
+fn test_installer_with_processors() {
+    let install_profile = r#"{"processors": [{"jar": "a:b:1"}, {"sides": ["server"]}]}"#;
+    let mut installer = zip_installer(&[("install_profile.json", install_profile)]);
+    assert!(installer.profile().is_err());
+    let dir = crate::TestDir::new("installer_with_processors");
+    assert!(installer.install(&dir).is_err());
+}
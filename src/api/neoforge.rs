@@ -0,0 +1,96 @@
+use super::forge::Installer;
+/// provide related function with the neoforge maven and installer
+/// https://neoforged.net
+use regex::Regex;
+
+/// builds of neoforge listed in the maven metadata, oldest first
+#[derive(Debug)]
+pub struct Metadata {
+    pub versions: Vec<String>,
+}
+
+impl Metadata {
+    /// fetch the builds of neoforge from a neoforge maven mirror, 1.20.1 is
+    /// published as `net.neoforged:forge`
+    /// # Examples
+    /// ```
+    /// use launcher::api::neoforge::Metadata;
+    /// let mirror = "https://maven.neoforged.net/releases/";
+    /// let _ = Metadata::fetch(mirror, "1.20.4").unwrap();
+    /// ```
+    pub fn fetch(mirror: &str, game_version: &str) -> anyhow::Result<Self> {
+        let url = format!("{mirror}{}/maven-metadata.xml", artifact(game_version));
+        let client = super::http_client();
+        let xml = fetch!(client, url, text)?;
+        Self::parse(&xml)
+    }
+
+    fn parse(xml: &str) -> anyhow::Result<Self> {
+        let regex = Regex::new(r"<version>\s*([^<\s]+)\s*</version>")?;
+        Ok(Metadata {
+            versions: regex.captures_iter(xml).map(|x| x[1].to_owned()).collect(),
+        })
+    }
+
+    /// return the latest build of `game_version`, builds of 1.20.2 and later
+    /// are numbered by the minor and patch game version like `20.4.80-beta`
+    pub fn latest(&self, game_version: &str) -> Option<String> {
+        let prefix = if game_version == "1.20.1" {
+            "1.20.1-".to_owned()
+        } else {
+            let mut parts = game_version.strip_prefix("1.")?.split('.');
+            let minor = parts.next()?;
+            let patch = parts.next().unwrap_or("0");
+            format!("{minor}.{patch}.")
+        };
+        self.versions
+            .iter()
+            .rfind(|x| x.starts_with(&prefix))
+            .cloned()
+    }
+}
+
+/// maven path of the neoforge artifact of `game_version`
+fn artifact(game_version: &str) -> &'static str {
+    if game_version == "1.20.1" {
+        "net/neoforged/forge"
+    } else {
+        "net/neoforged/neoforge"
+    }
+}
+
+/// fetch the installer of neoforge `build` for `game_version` from a
+/// neoforge maven mirror, it has the format of the forge installer
+/// # Examples
+/// ```
+/// use launcher::api::neoforge;
+/// let mirror = "https://maven.neoforged.net/releases/";
+/// let _ = neoforge::installer(mirror, "1.20.4", "20.4.80-beta").unwrap();
+/// ```
+pub fn installer(mirror: &str, game_version: &str, build: &str) -> anyhow::Result<Installer> {
+    let path = artifact(game_version);
+    let file = path.rsplit('/').next().unwrap_or_default();
+    let url = format!("{mirror}{path}/{build}/{file}-{build}-installer.jar");
+    Installer::fetch_url(format!("neoforge {build}"), url)
+}
+
+#[test]
+fn test_latest_neoforge() {
+    let metadata = Metadata::parse(
+        "<metadata><versioning><versions>
+            <version>20.4.79-beta</version>
+            <version>20.4.80-beta</version>
+            <version>21.0.0-beta</version>
+            <version>21.1.72</version>
+        </versions></versioning></metadata>",
+    )
+    .unwrap();
+    assert_eq!(metadata.versions.len(), 4);
+    assert_eq!(metadata.latest("1.20.4").unwrap(), "20.4.80-beta");
+    assert_eq!(metadata.latest("1.21").unwrap(), "21.0.0-beta");
+    assert_eq!(metadata.latest("1.21.1").unwrap(), "21.1.72");
+    assert!(metadata.latest("1.20.5").is_none());
+    assert!(metadata.latest("24w03a").is_none());
+    let metadata = Metadata::parse("<version>1.20.1-47.1.106</version>").unwrap();
+    assert_eq!(metadata.latest("1.20.1").unwrap(), "1.20.1-47.1.106");
+}
@@ -1,17 +1,31 @@
 /// provide related function with minecraft official api
 use super::{DomainReplacer, Sha1Compare};
 use crate::config::VersionType;
+use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
-use std::{collections::HashMap, fs, path::Path};
+use std::{
+    collections::BTreeMap,
+    fs,
+    path::Path,
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
 
-#[cfg(target_os = "windows")]
-const OS: &str = "windows";
-
-#[cfg(target_os = "linux")]
-const OS: &str = "linux";
+/// return current os name in mojang's naming (`windows`, `osx`, `linux`)
+pub fn os_name() -> &'static str {
+    match std::env::consts::OS {
+        "macos" => "osx",
+        os => os,
+    }
+}
 
-#[cfg(target_os = "macos")]
-const OS: &str = "osx";
+/// return current cpu architecture in mojang's naming (`x86`, `x86_64`,
+/// `arm64`), used by the `os.arch` rules and the native classifiers
+pub fn arch_name() -> &'static str {
+    match std::env::consts::ARCH {
+        "aarch64" => "arm64",
+        arch => arch,
+    }
+}
 
 // version json libraries
 #[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
@@ -24,22 +38,92 @@ pub struct Artifact {
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct LibDownloads {
-    pub artifact: Artifact,
-    pub classifiers: Option<HashMap<String, Artifact>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub artifact: Option<Artifact>,
+    pub classifiers: Option<BTreeMap<String, Artifact>>,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
 pub struct Rules {
     pub action: String,
-    pub os: Option<HashMap<String, String>>,
+    pub os: Option<BTreeMap<String, String>>,
+    /// launcher features of argument rules, such as `is_demo_user`
+    pub features: Option<BTreeMap<String, bool>>,
+}
+
+/// return true if the rules allow current os
+/// the last matched rule decides, and nothing is allowed if no rule matched
+/// rules asking for a launcher feature never match, the features are set
+/// through the launch options instead
+/// # Examples
+/// ```
+/// use launcher::api::official::{rules_match, Rules};
+/// let rules = vec![Rules {
+///     action: "allow".into(),
+///     os: None,
+///     features: None,
+/// }];
+/// assert!(rules_match(&rules));
+/// ```
+pub fn rules_match(rules: &[Rules]) -> bool {
+    rules_match_on(rules, os_name(), arch_name())
+}
+
+fn rules_match_on(rules: &[Rules], os: &str, arch: &str) -> bool {
+    let mut allow = false;
+    for rule in rules {
+        let os_rule = rule.os.as_ref();
+        let matched = os_rule
+            .and_then(|x| x.get("name"))
+            .is_none_or(|name| name == os)
+            && os_rule
+                .and_then(|x| x.get("arch"))
+                .is_none_or(|x| x == arch)
+            && rule
+                .features
+                .as_ref()
+                .is_none_or(|x| x.values().all(|enabled| !enabled));
+        if matched {
+            allow = rule.action == "allow";
+        }
+    }
+    allow
+}
+
+#[test]
+fn test_rules_match() {
+    let rules: Vec<Rules> = serde_json::from_str(
+        r#"[{"action": "allow"}, {"action": "disallow", "os": {"name": "osx"}}]"#,
+    )
+    .unwrap();
+    assert!(!rules_match_on(&rules, "osx", "x86_64"));
+    assert!(rules_match_on(&rules, "linux", "x86_64"));
+    let rules: Vec<Rules> =
+        serde_json::from_str(r#"[{"action": "allow", "features": {"is_demo_user": true}}]"#)
+            .unwrap();
+    assert!(!rules_match_on(&rules, "linux", "x86_64"));
+    let rules: Vec<Rules> =
+        serde_json::from_str(r#"[{"action": "allow", "os": {"arch": "x86"}}]"#).unwrap();
+    assert!(rules_match_on(&rules, "windows", "x86"));
+    assert!(!rules_match_on(&rules, "windows", "arm64"));
+}
+
+/// files to skip when extracting a native library
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct Extract {
+    #[serde(default)]
+    pub exclude: Vec<String>,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct Library {
-    pub downloads: LibDownloads,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub downloads: Option<LibDownloads>,
     pub name: String,
-    pub natives: Option<HashMap<String, String>>,
+    pub natives: Option<BTreeMap<String, String>>,
     pub rules: Option<Vec<Rules>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub extract: Option<Extract>,
 }
 
 impl Library {
@@ -56,14 +140,41 @@ impl Library {
     /// assert!(targets.len() > 0);
     /// ```
     pub fn is_target_lib(&self) -> bool {
-        if let Some(rule) = &self.rules {
-            let flag = rule
-                .iter()
-                .find(|x| x.os.clone().unwrap_or_default()["name"] == OS);
-            self.downloads.classifiers.is_none() && flag.is_some()
+        // lwjgl 3 of 1.13 to 1.18 has both the jar and its natives, lwjgl 2
+        // only has the natives
+        let classifiers = self.downloads.as_ref().and_then(|x| x.classifiers.as_ref());
+        (classifiers.is_none() || self.artifact().is_some())
+            && self.rules.as_deref().is_none_or(rules_match)
+    }
+
+    /// return the main artifact of the library, or `None` if the library
+    /// only has a name and no `downloads.artifact`
+    pub fn artifact(&self) -> Option<&Artifact> {
+        self.downloads.as_ref().and_then(|x| x.artifact.as_ref())
+    }
+
+    /// return the native classifier artifact for current os, or `None` if
+    /// the library has no natives for it
+    pub fn native_artifact(&self) -> Option<&Artifact> {
+        self.native_artifact_on(os_name(), arch_name())
+    }
+
+    /// `${arch}` in the classifier of `natives.<os>` is the bitness, like
+    /// `natives-windows-${arch}` of the twitch natives, other architectures
+    /// than x86_64 prefer the classifier with their name appended, like
+    /// `natives-linux-arm64`
+    fn native_artifact_on(&self, os: &str, arch: &str) -> Option<&Artifact> {
+        let bits = if cfg!(target_pointer_width = "64") {
+            "64"
         } else {
-            self.downloads.classifiers.is_none()
-        }
+            "32"
+        };
+        let classifier = self.natives.as_ref()?.get(os)?.replace("${arch}", bits);
+        let classifiers = self.downloads.as_ref()?.classifiers.as_ref()?;
+        let own_arch = (arch != "x86_64")
+            .then(|| classifiers.get(&format!("{classifier}-{arch}")))
+            .flatten();
+        own_arch.or_else(|| classifiers.get(&classifier))
     }
 
     /// return true if is required native
@@ -79,20 +190,140 @@ impl Library {
     /// assert!(targets.len() > 0);
     /// ```
     pub fn is_target_native(&self) -> bool {
-        self.natives.as_ref().and_then(|x| x.get(OS)).is_some()
+        self.natives
+            .as_ref()
+            .and_then(|x| x.get(os_name()))
+            .is_some()
+            && self.rules.as_deref().is_none_or(rules_match)
     }
 }
 
+#[test]
+fn test_native_artifact() {
+    let library: Library = serde_json::from_value(serde_json::json!({
+        "name": "tv.twitch:twitch-platform:5.16",
+        "downloads": {"classifiers": {
+            "natives-linux": {"path": "linux.jar", "url": ""},
+            "natives-windows-32": {"path": "windows-32.jar", "url": ""},
+            "natives-windows-64": {"path": "windows-64.jar", "url": ""}
+        }},
+        "natives": {"linux": "natives-linux", "windows": "natives-windows-${arch}"}
+    }))
+    .unwrap();
+    assert!(!library.is_target_lib());
+    assert_eq!(
+        library.native_artifact_on("linux", "x86_64").unwrap().path,
+        "linux.jar"
+    );
+    let windows = if cfg!(target_pointer_width = "64") {
+        "windows-64.jar"
+    } else {
+        "windows-32.jar"
+    };
+    assert_eq!(
+        library
+            .native_artifact_on("windows", "x86_64")
+            .unwrap()
+            .path,
+        windows
+    );
+    assert!(library.native_artifact_on("osx", "x86_64").is_none());
+    let library: Library = serde_json::from_value(serde_json::json!({
+        "name": "org.lwjgl:lwjgl:3.2.2",
+        "downloads": {
+            "artifact": {"path": "lwjgl.jar", "url": ""},
+            "classifiers": {"natives-linux": {"path": "linux.jar", "url": ""}}
+        },
+        "natives": {"linux": "natives-linux"}
+    }))
+    .unwrap();
+    assert!(library.is_target_lib());
+}
+
+#[test]
+fn test_native_artifact_arch() {
+    let library: Library = serde_json::from_value(serde_json::json!({
+        "name": "org.lwjgl:lwjgl:3.3.1",
+        "downloads": {"classifiers": {
+            "natives-linux": {"path": "linux.jar", "url": ""},
+            "natives-linux-arm64": {"path": "linux-arm64.jar", "url": ""}
+        }},
+        "natives": {"linux": "natives-linux", "osx": "natives-macos"}
+    }))
+    .unwrap();
+    let path = |arch| {
+        library
+            .native_artifact_on("linux", arch)
+            .unwrap()
+            .path
+            .clone()
+    };
+    assert_eq!(path("x86_64"), "linux.jar");
+    assert_eq!(path("arm64"), "linux-arm64.jar");
+    assert_eq!(path("x86"), "linux.jar");
+    if std::env::consts::ARCH == "aarch64" {
+        assert_eq!(library.native_artifact().unwrap().path, "linux-arm64.jar");
+    }
+    assert!(library.native_artifact_on("osx", "arm64").is_none());
+}
+
 pub type Libraries = Vec<Library>;
 
-#[derive(Debug, Serialize, Deserialize)]
+/// serde of the rfc 3339 times of the version manifest
+mod rfc3339 {
+    use chrono::{DateTime, Utc};
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(
+        time: &DateTime<Utc>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&time.to_rfc3339())
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<DateTime<Utc>, D::Error> {
+        let text = String::deserialize(deserializer)?;
+        DateTime::parse_from_rfc3339(&text)
+            .map(|x| x.with_timezone(&Utc))
+            .map_err(|e| serde::de::Error::custom(format!("invalid time {text}: {e}")))
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Versions {
     pub id: String,
     pub r#type: String,
     pub url: String,
-    pub time: String,
-    #[serde[rename = "releaseTime"]]
-    pub release_time: String,
+    /// last time the version json was updated
+    #[serde(with = "rfc3339")]
+    pub time: DateTime<Utc>,
+    #[serde(rename = "releaseTime", with = "rfc3339")]
+    pub release_time: DateTime<Utc>,
+}
+
+impl Versions {
+    /// return true if the version was released after `date`
+    pub fn released_after(&self, date: DateTime<Utc>) -> bool {
+        self.release_time > date
+    }
+
+    /// return true if the version was released before `date`
+    pub fn released_before(&self, date: DateTime<Utc>) -> bool {
+        self.release_time < date
+    }
+}
+
+/// parse an iso 8601 date like `2024-01-15`, which is midnight in utc, or a
+/// rfc 3339 time like `2024-01-15T12:00:00Z`
+pub fn parse_date(text: &str) -> anyhow::Result<DateTime<Utc>> {
+    if let Ok(time) = DateTime::parse_from_rfc3339(text) {
+        return Ok(time.with_timezone(&Utc));
+    }
+    let date = chrono::NaiveDate::parse_from_str(text, "%Y-%m-%d")
+        .map_err(|_| anyhow::anyhow!("invalid date {text}, expected YYYY-MM-DD"))?;
+    Ok(date.and_time(chrono::NaiveTime::MIN).and_utc())
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -107,6 +338,21 @@ pub struct VersionManifest {
     pub versions: Vec<Versions>,
 }
 
+/// version manifest saved by `VersionManifest::fetch_cached`
+#[derive(Debug, Serialize, Deserialize)]
+struct CachedManifest {
+    /// unix time of the fetch in seconds
+    fetched_at: u64,
+    mirror: String,
+    manifest: VersionManifest,
+}
+
+fn unix_time() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |x| x.as_secs())
+}
+
 impl VersionManifest {
     /// fetch mc official version manifest based on mirror
     /// # Examples
@@ -117,11 +363,71 @@ impl VersionManifest {
     /// ```
     pub fn fetch(mirror: &str) -> anyhow::Result<Self> {
         let url = mirror.to_owned() + "mc/game/version_manifest.json";
-        let client = reqwest::blocking::Client::new();
+        let client = super::http_client();
         fetch!(client, url, json)
     }
 
-    /// fetch version list fromm manifest
+    /// return the manifest cached in `cache_file` if it was fetched from
+    /// `mirror` less than `ttl` ago, else fetch it and update the cache, a
+    /// stale cache is used with a warning when the fetch fails
+    /// `refresh` always fetches
+    pub fn fetch_cached(
+        mirror: &str,
+        cache_file: &Path,
+        ttl: Duration,
+        refresh: bool,
+    ) -> anyhow::Result<Self> {
+        let cached = fs::read_to_string(cache_file)
+            .ok()
+            .and_then(|x| serde_json::from_str::<CachedManifest>(&x).ok())
+            .filter(|x| x.mirror == mirror);
+        let is_fresh = |x: &CachedManifest| {
+            !refresh && unix_time().saturating_sub(x.fetched_at) < ttl.as_secs()
+        };
+        let cached = match cached {
+            Some(x) if is_fresh(&x) => return Ok(x.manifest),
+            x => x,
+        };
+        match Self::fetch(mirror) {
+            Ok(manifest) => {
+                let cache = CachedManifest {
+                    fetched_at: unix_time(),
+                    mirror: mirror.to_owned(),
+                    manifest,
+                };
+                let text = serde_json::to_string(&cache)?;
+                let write = fs::create_dir_all(cache_file.parent().unwrap())
+                    .and_then(|_| fs::write(cache_file, text));
+                if let Err(e) = write {
+                    log::warn!("can not cache the version manifest: {e}");
+                }
+                Ok(cache.manifest)
+            }
+            Err(e) => match cached {
+                Some(cached) => {
+                    log::warn!("{e}, use the version manifest cached before");
+                    Ok(cached.manifest)
+                }
+                None => Err(e),
+            },
+        }
+    }
+
+    /// download the version manifest from mirror once, and return how long
+    /// it takes
+    pub fn benchmark(mirror: &str) -> anyhow::Result<Duration> {
+        let url = mirror.to_owned() + "mc/game/version_manifest.json";
+        let start = Instant::now();
+        super::http_client()
+            .get(&url)
+            .timeout(Duration::from_secs(10))
+            .send()?
+            .error_for_status()?
+            .bytes()?;
+        Ok(start.elapsed())
+    }
+
+    /// fetch version list fromm manifest, newest first
     /// # Examples
     /// ```
     /// use launcher::api::official::VersionManifest;
@@ -136,45 +442,222 @@ impl VersionManifest {
     /// assert!(snapshot.len() > 0);
     /// ```
     pub fn list(&self, version_type: VersionType) -> Vec<String> {
-        match version_type {
-            VersionType::All => self.versions.iter().map(|x| x.id.clone()).collect(),
-            VersionType::Release => self
-                .versions
-                .iter()
-                .filter(|x| x.r#type == "release")
-                .map(|x| x.id.clone())
-                .collect(),
-            VersionType::Snapshot => self
-                .versions
-                .iter()
-                .filter(|x| x.r#type == "snapshot")
-                .map(|x| x.id.clone())
-                .collect(),
+        self.versions(version_type)
+            .into_iter()
+            .map(|x| x.id.clone())
+            .collect()
+    }
+
+    /// return versions of `version_type`, newest first
+    pub fn versions(&self, version_type: VersionType) -> Vec<&Versions> {
+        let mut versions: Vec<&Versions> = self
+            .versions
+            .iter()
+            .filter(|x| {
+                version_type
+                    .manifest_type()
+                    .is_none_or(|r#type| x.r#type == r#type)
+            })
+            .collect();
+        versions.sort_by_key(|x| std::cmp::Reverse(x.release_time));
+        versions
+    }
+
+    /// resolve the keywords `latest` and `latest-snapshot` to the id of the
+    /// newest release and snapshot, other versions are returned unchanged
+    pub fn resolve<'a>(&'a self, version: &'a str) -> &'a str {
+        match version {
+            "latest" => &self.latest.release,
+            "latest-snapshot" => &self.latest.snapshot,
+            _ => version,
         }
     }
 
+    /// return an Error if `version` is not in the manifest, suggesting the
+    /// closest ids
+    pub fn check(&self, version: &str) -> anyhow::Result<()> {
+        if self.versions.iter().any(|x| x.id == version) {
+            return Ok(());
+        }
+        let suggestions = self.suggest(version);
+        if suggestions.is_empty() {
+            anyhow::bail!("unknown version {version}, run `list mc` to see the versions");
+        }
+        anyhow::bail!(
+            "unknown version {version}, did you mean {}?",
+            suggestions.join(", ")
+        )
+    }
+
+    /// return up to 3 ids which are at most 2 edits away from `version`,
+    /// closest and newest first
+    pub fn suggest(&self, version: &str) -> Vec<&str> {
+        let mut close: Vec<_> = self
+            .versions(VersionType::All)
+            .into_iter()
+            .map(|x| (edit_distance(&x.id, version), x.id.as_str()))
+            .filter(|x| x.0 <= 2)
+            .collect();
+        close.sort_by_key(|x| x.0);
+        close.into_iter().take(3).map(|x| x.1).collect()
+    }
+
     /// fetch url based on version
     /// attention: the url provided by official
-    /// if version not exist then panic
+    /// # Error
+    /// Return Error if the version is not in the manifest
     /// # Examples
     /// ```
     /// use launcher::api::official::VersionManifest;
     /// use launcher::config::VersionType;
     /// let mirror = "https://bmclapi2.bangbang93.com/";
     /// let manifest = VersionManifest::fetch(mirror).unwrap();
-    /// let url = manifest.url("1.20.4");
+    /// let url = manifest.url("1.20.4").unwrap();
     /// assert!(url.len() > 0);
+    /// assert!(manifest.url("no_game_version").is_err());
     /// ```
-    pub fn url(&self, version: &str) -> String {
+    pub fn url(&self, version: &str) -> anyhow::Result<String> {
         self.versions
             .iter()
             .find(|x| x.id == version)
-            .unwrap()
-            .url
-            .to_owned()
+            .map(|x| x.url.to_owned())
+            .ok_or_else(|| anyhow::anyhow!("version {version} not found in manifest"))
     }
 }
 
+/// return the levenshtein distance of `a` and `b` in chars
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut row: Vec<usize> = (0..=b.len()).collect();
+    for (i, x) in a.chars().enumerate() {
+        let mut diagonal = row[0];
+        row[0] = i + 1;
+        for (j, y) in b.iter().enumerate() {
+            let substitute = diagonal + usize::from(x != *y);
+            diagonal = row[j + 1];
+            row[j + 1] = substitute.min(row[j] + 1).min(diagonal + 1);
+        }
+    }
+    row[b.len()]
+}
+
+#[test]
+fn test_edit_distance() {
+    assert_eq!(edit_distance("1.20.4", "1.20.4"), 0);
+    assert_eq!(edit_distance("1.20.4", "1.20.44"), 1);
+    assert_eq!(edit_distance("1.20.4", "1.2.4"), 1);
+    assert_eq!(edit_distance("1.20.4", "1.20.3"), 1);
+}
+
+#[test]
+fn test_parse_date() {
+    let date = parse_date("2024-01-15").unwrap();
+    assert_eq!(date.to_rfc3339(), "2024-01-15T00:00:00+00:00");
+    let time = parse_date("2024-01-15T14:12:26+08:00").unwrap();
+    assert_eq!(time.to_rfc3339(), "2024-01-15T06:12:26+00:00");
+    assert!(parse_date("15.01.2024").is_err());
+}
+
+#[cfg(test)]
+fn test_manifest() -> VersionManifest {
+    serde_json::from_value(serde_json::json!({
+        "latest": {"release": "1.20.4", "snapshot": "24w03a"},
+        "versions": [
+            {"id": "1.20.3", "type": "release", "url": "", "time": "2023-12-05T12:10:32+00:00",
+             "releaseTime": "2023-12-05T12:10:32+00:00"},
+            {"id": "24w03a", "type": "snapshot", "url": "", "time": "2024-01-17T14:12:26+00:00",
+             "releaseTime": "2024-01-17T14:12:26+00:00"},
+            {"id": "1.20.4", "type": "release", "url": "", "time": "2023-12-07T12:56:20+00:00",
+             "releaseTime": "2023-12-07T12:56:20+00:00"},
+            {"id": "b1.7.3", "type": "old_beta", "url": "", "time": "2011-07-07T22:00:00+00:00",
+             "releaseTime": "2011-07-07T22:00:00+00:00"},
+            {"id": "a1.2.6", "type": "old_alpha", "url": "", "time": "2010-12-02T22:00:00+00:00",
+             "releaseTime": "2010-12-02T22:00:00+00:00"}
+        ]
+    }))
+    .unwrap()
+}
+
+#[test]
+fn test_versions_newest_first() {
+    let manifest = test_manifest();
+    assert_eq!(
+        manifest.list(VersionType::All),
+        ["24w03a", "1.20.4", "1.20.3", "b1.7.3", "a1.2.6"]
+    );
+    assert_eq!(manifest.list(VersionType::Release), ["1.20.4", "1.20.3"]);
+    assert_eq!(manifest.list(VersionType::OldBeta), ["b1.7.3"]);
+    assert_eq!(manifest.list(VersionType::OldAlpha), ["a1.2.6"]);
+    let invalid = r#"{"id": "x", "type": "release", "url": "", "time": "", "releaseTime": ""}"#;
+    assert!(serde_json::from_str::<Versions>(invalid).is_err());
+}
+
+#[test]
+fn test_resolve_latest() {
+    let manifest = test_manifest();
+    assert_eq!(manifest.resolve("latest"), "1.20.4");
+    assert_eq!(manifest.resolve("latest-snapshot"), "24w03a");
+    assert_eq!(manifest.resolve("1.20.3"), "1.20.3");
+}
+
+#[test]
+fn test_check_suggests() {
+    let manifest = test_manifest();
+    assert!(manifest.check("1.20.4").is_ok());
+    let unknown = manifest.check("1.20.44").unwrap_err().to_string();
+    assert_eq!(
+        unknown,
+        "unknown version 1.20.44, did you mean 1.20.4, 1.20.3?"
+    );
+    let unknown = manifest.check("foo").unwrap_err().to_string();
+    assert!(unknown.contains("list mc"));
+}
+
+#[test]
+fn test_url_missing() {
+    let manifest = test_manifest();
+    assert!(manifest.url("1.20.4").is_ok());
+    let unknown = manifest.url("1.20.44").unwrap_err().to_string();
+    assert_eq!(unknown, "version 1.20.44 not found in manifest");
+}
+
+#[test]
+fn test_release_date_filters() {
+    let manifest = test_manifest();
+    let date = "2023-12-06T00:00:00Z".parse().unwrap();
+    let after: Vec<_> = manifest
+        .versions(VersionType::All)
+        .into_iter()
+        .filter(|x| x.released_after(date))
+        .map(|x| x.id.as_str())
+        .collect();
+    assert_eq!(after, ["24w03a", "1.20.4"]);
+    let before = parse_date("2024-01-01").unwrap();
+    let between: Vec<_> = manifest
+        .versions(VersionType::Release)
+        .into_iter()
+        .filter(|x| x.released_after(date) && x.released_before(before))
+        .map(|x| x.id.as_str())
+        .collect();
+    assert_eq!(between, ["1.20.4"]);
+}
+
+#[test]
+fn test_fetch_cached() {
+    let dir = crate::TestDir::new("fetch_cached");
+    let file = dir.join("manifest.json");
+    let mirror = "https://127.0.0.1/";
+    let cache = serde_json::json!({
+        "fetched_at": unix_time(),
+        "mirror": mirror,
+        "manifest": {"latest": {"release": "1.20.4", "snapshot": "24w03a"}, "versions": []}
+    });
+    fs::write(&file, cache.to_string()).unwrap();
+    let ttl = Duration::from_secs(3600);
+    let manifest = VersionManifest::fetch_cached(mirror, &file, ttl, false).unwrap();
+    assert_eq!(manifest.latest.release, "1.20.4");
+}
+
 /// asset index in version.json
 #[derive(Debug, Serialize, Deserialize)]
 pub struct AssetIndex {
@@ -194,9 +677,9 @@ pub struct Asset {
 
 /// assets.json
 /// which from minecraftfile/assets/indexes/'id'.json
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, Default)]
 pub struct Assets {
-    pub objects: HashMap<String, Asset>,
+    pub objects: BTreeMap<String, Asset>,
 }
 
 impl Assets {
@@ -212,28 +695,35 @@ impl Assets {
     /// let version = Version::fetch(manifest, "1.20.4", manifest_mirror).unwrap();
     /// let _ = Assets::fetch(&version.asset_index, assets_mirror).unwrap();
     /// ```
+    /// an asset index without sha1 is fetched unverified
     pub fn fetch(asset_index: &AssetIndex, mirror: &str) -> anyhow::Result<Self> {
         let url = asset_index.url.replace_domain(mirror);
-        let client = reqwest::blocking::Client::new();
+        let client = super::http_client();
         let sha1 = &asset_index.sha1;
-        let data = fetch!(client, url, sha1, text)?;
+        let data = if sha1.is_empty() {
+            fetch!(client, url, text)?
+        } else {
+            fetch!(client, url, sha1, text)?
+        };
         Ok(serde_json::from_str(&data)?)
     }
 
-    /// install Assets
-    pub fn install<P>(&self, file: &P)
+    /// install Assets, an identical file is kept, return true if the file
+    /// was written
+    pub fn install<P>(&self, file: &P) -> bool
     where
         P: AsRef<Path>,
     {
         let text = serde_json::to_string_pretty(self).unwrap();
-        fs::create_dir_all(file.as_ref().parent().unwrap()).unwrap();
-        fs::write(file, text).unwrap();
+        super::write_if_changed(file.as_ref(), text.as_bytes()).unwrap()
     }
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, Default)]
 pub struct Arguments {
+    #[serde(default)]
     pub game: Vec<serde_json::Value>,
+    #[serde(default)]
     pub jvm: Vec<serde_json::Value>,
 }
 
@@ -241,13 +731,20 @@ pub struct Arguments {
 /// which from minecraftfile/versions/'version'/'version'.json
 #[derive(Debug, Serialize, Deserialize)]
 pub struct Version {
-    pub arguments: Arguments,
+    /// arguments of 1.13 and later
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub arguments: Option<Arguments>,
+    /// game arguments before 1.13
+    #[serde(rename = "minecraftArguments")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub minecraft_arguments: Option<String>,
     #[serde(rename = "assetIndex")]
     pub asset_index: AssetIndex,
     pub assets: String,
     #[serde(rename = "complianceLevel")]
     pub compliance_level: usize,
-    pub downloads: serde_json::Value,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub downloads: Option<serde_json::Value>,
     pub id: String,
     #[serde(rename = "javaVersion")]
     pub java_version: serde_json::Value,
@@ -263,11 +760,62 @@ pub struct Version {
     pub r#type: String,
 }
 
+/// version json of release `id` with `libraries` and the asset index `12`,
+/// the fields tests do not look at are placeholders
+#[cfg(test)]
+pub(crate) fn test_version_json(id: &str, libraries: serde_json::Value) -> serde_json::Value {
+    serde_json::json!({
+        "assetIndex": {"totalSize": 0, "id": "12", "url": "", "sha1": "", "size": 0},
+        "assets": "12",
+        "complianceLevel": 1,
+        "id": id,
+        "javaVersion": {"majorVersion": 17},
+        "libraries": libraries,
+        "logging": {},
+        "mainClass": "net.minecraft.client.main.Main",
+        "minimumLauncherVersion": 21,
+        "releaseTime": "",
+        "time": "",
+        "type": "release"
+    })
+}
+
+/// parsed `test_version_json`
+#[cfg(test)]
+pub(crate) fn test_version(id: &str, libraries: serde_json::Value) -> Version {
+    serde_json::from_value(test_version_json(id, libraries)).unwrap()
+}
+
+#[test]
+fn test_install_keeps_identical() {
+    let dir = crate::TestDir::new("install_keeps_identical");
+    let file = dir.join("1.12.2.json");
+    let version = test_version(
+        "1.12.2",
+        serde_json::json!([{
+            "name": "org.lwjgl.lwjgl:lwjgl-platform:2.9.4-nightly-20150209",
+            "downloads": {"classifiers": {
+                "natives-linux": {"path": "a", "sha1": "", "size": 0, "url": ""},
+                "natives-osx": {"path": "b", "sha1": "", "size": 0, "url": ""},
+                "natives-windows": {"path": "c", "sha1": "", "size": 0, "url": ""}
+            }},
+            "natives": {"linux": "natives-linux", "osx": "natives-osx", "windows": "natives-windows"}
+        }]),
+    );
+    assert!(version.install(&file));
+    let version: Version = serde_json::from_slice(&fs::read(&file).unwrap()).unwrap();
+    assert!(!version.install(&file));
+}
+
 pub trait MergeVersion {
     fn official_libraries(&self) -> Option<Vec<Library>>;
     fn main_class(&self) -> Option<String>;
     fn arguments_game(&self) -> Option<Vec<serde_json::Value>>;
     fn arguments_jvm(&self) -> Option<Vec<serde_json::Value>>;
+    /// replace the game arguments of versions before 1.13
+    fn minecraft_arguments(&self) -> Option<String> {
+        None
+    }
 }
 
 impl Version {
@@ -280,22 +828,33 @@ impl Version {
     /// let _ = Version::fetch(manifest, "1.20.4", manifest_mirror).unwrap();
     /// ```
     pub fn fetch(manifest: VersionManifest, version: &str, mirror: &str) -> anyhow::Result<Self> {
-        let url = manifest.url(version).replace_domain(mirror);
-        let client = reqwest::blocking::Client::new();
+        let url = manifest.url(version)?.replace_domain(mirror);
+        let client = super::http_client();
         fetch!(client, url, json)
     }
 
-    /// install version json
-    pub fn install<P>(&self, file: &P)
+    /// install version json, an identical file is kept, return true if the file
+    /// was written
+    pub fn install<P>(&self, file: &P) -> bool
     where
         P: AsRef<Path>,
     {
         let text = serde_json::to_string_pretty(self).unwrap();
-        fs::create_dir_all(file.as_ref().parent().unwrap()).unwrap();
-        fs::write(file, text).unwrap();
+        super::write_if_changed(file.as_ref(), text.as_bytes()).unwrap()
+    }
+
+    /// return true if the version supports quick play, which mojang added
+    /// in 1.20 as the `--quickPlay*` game arguments
+    pub fn supports_quick_play(&self) -> bool {
+        self.arguments.as_ref().is_some_and(|x| {
+            x.game
+                .iter()
+                .any(|arg| arg.to_string().contains("--quickPlayMultiplayer"))
+        })
     }
 
     /// merge other api such as fabric prifile and official version json
+    /// libraries of `other` are put ahead of the official ones
     /// # Examples
     /// ```
     /// use launcher::api::official;
@@ -325,16 +884,22 @@ impl Version {
         T: MergeVersion,
     {
         if let Some(mut libs) = other.official_libraries() {
-            self.libraries.append(&mut libs);
+            libs.append(&mut self.libraries);
+            self.libraries = libs;
         }
         if let Some(main_class) = other.main_class() {
             self.main_class = main_class;
         }
         if let Some(mut arguments_game) = other.arguments_game() {
-            self.arguments.game.append(&mut arguments_game)
+            let arguments = self.arguments.get_or_insert_with(Arguments::default);
+            arguments.game.append(&mut arguments_game)
         }
         if let Some(mut arguments_jvm) = other.arguments_jvm() {
-            self.arguments.jvm.append(&mut arguments_jvm)
+            let arguments = self.arguments.get_or_insert_with(Arguments::default);
+            arguments.jvm.append(&mut arguments_jvm)
+        }
+        if let Some(minecraft_arguments) = other.minecraft_arguments() {
+            self.minecraft_arguments = Some(minecraft_arguments);
         }
     }
 }
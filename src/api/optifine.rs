@@ -0,0 +1,295 @@
+use super::{official, to_path};
+/// install optifine standalone, the jar is a library loaded by launchwrapper
+/// with the optifine tweaker
+/// https://optifine.net
+use regex::Regex;
+use sha1::{Digest, Sha1};
+use std::{
+    fs,
+    io::{Cursor, Read},
+    path::Path,
+};
+
+const LAUNCH_CLASS: &str = "net.minecraft.launchwrapper.Launch";
+const TWEAK_CLASS: &str = "optifine.OptiFineTweaker";
+/// launchwrapper of the official libraries, for jars without launchwrapper-of
+const LAUNCHWRAPPER: &str = "net.minecraft:launchwrapper:1.12";
+
+/// version profile of optifine, merged into the official version json
+#[derive(Debug)]
+pub struct Profile {
+    libraries: Vec<official::Library>,
+    minecraft_arguments: Option<String>,
+    arguments_game: Option<Vec<serde_json::Value>>,
+}
+
+/// optifine jar, either the download of optifine.net or the `_MOD.jar` its
+/// installer extracts
+pub struct OptiFine {
+    /// game version in the file name, like `1.12.2`
+    pub game_version: String,
+    /// edition in the file name, like `HD_U_G5`
+    pub edition: String,
+    name: String,
+    data: bytes::Bytes,
+    archive: zip::ZipArchive<Cursor<bytes::Bytes>>,
+}
+
+impl OptiFine {
+    /// read the optifine jar at `source`, which is a local path or an url
+    /// # Error
+    /// Return Error if the file name is not like `OptiFine_1.12.2_HD_U_G5.jar`,
+    /// the game version and edition are only known from it
+    pub fn fetch(source: &str) -> anyhow::Result<Self> {
+        let name = source
+            .split(['?', '#'])
+            .next()
+            .unwrap_or_default()
+            .rsplit(['/', '\\'])
+            .next()
+            .unwrap_or_default();
+        let data = if source.starts_with("http://") || source.starts_with("https://") {
+            let url = source.to_owned();
+            let client = super::http_client();
+            fetch!(client, url, bytes)?
+        } else {
+            fs::read(source)
+                .map_err(|e| anyhow::anyhow!("can not read optifine jar {source}: {e}"))?
+                .into()
+        };
+        Self::new(name, data)
+    }
+
+    fn new(name: &str, data: bytes::Bytes) -> anyhow::Result<Self> {
+        let regex =
+            Regex::new(r"^(?:preview_)?OptiFine_(\d+(?:\.\d+)+)_(HD_U_\w+?)(?:_MOD)?\.jar$")?;
+        let captures = regex.captures(name).ok_or_else(|| {
+            anyhow::anyhow!(
+                "can not tell the game version of {name}, keep the original file name \
+                 like OptiFine_1.12.2_HD_U_G5.jar"
+            )
+        })?;
+        let archive = zip::ZipArchive::new(Cursor::new(data.clone()))
+            .map_err(|e| anyhow::anyhow!("{name} is not a jar: {e}"))?;
+        // classes shipped as xdelta patches are only usable after the
+        // optifine installer applied them to the vanilla client
+        if archive
+            .file_names()
+            .any(|x| x.is_ok_and(|x| x.ends_with(".xdelta")))
+        {
+            anyhow::bail!(
+                "{name} needs the optifine installer to patch the client, which is not supported, \
+                 run `java -jar {name}`, choose Extract and install the extracted _MOD.jar"
+            );
+        }
+        Ok(OptiFine {
+            game_version: captures[1].to_owned(),
+            edition: captures[2].to_owned(),
+            name: name.to_owned(),
+            data,
+            archive,
+        })
+    }
+
+    /// return the profile which launches `version` through launchwrapper
+    /// with the optifine tweaker, and copy the jar and the bundled
+    /// launchwrapper into `libraries_dir`, or skip that without it
+    /// # Error
+    /// Return Error if the jar is for another game version, or `version` is
+    /// 1.13 or later and the jar does not bundle launchwrapper-of
+    pub fn install(
+        &mut self,
+        version: &official::Version,
+        libraries_dir: Option<&Path>,
+    ) -> anyhow::Result<Profile> {
+        if self.game_version != version.id {
+            anyhow::bail!(
+                "{} is for minecraft {}, not {}",
+                self.name,
+                self.game_version,
+                version.id
+            );
+        }
+        let name = format!("optifine:OptiFine:{}_{}", self.game_version, self.edition);
+        let data = self.data.clone();
+        let mut libraries = vec![bundled_library(&name, &data, libraries_dir)?];
+        match self.read("launchwrapper-of.txt") {
+            Ok(launchwrapper) => {
+                let launchwrapper = String::from_utf8(launchwrapper)?;
+                let launchwrapper = launchwrapper.trim();
+                let data = self.read(&format!("launchwrapper-of-{launchwrapper}.jar"))?;
+                let name = format!("optifine:launchwrapper-of:{launchwrapper}");
+                libraries.push(bundled_library(&name, &data, libraries_dir)?);
+            }
+            // launchwrapper 1.12 does not run on the java of 1.13 and later
+            Err(_) if version.arguments.is_some() => anyhow::bail!(
+                "{} does not bundle launchwrapper-of, which minecraft {} needs",
+                self.name,
+                version.id
+            ),
+            Err(_) => libraries.push(library(
+                LAUNCHWRAPPER,
+                format!("https://libraries.minecraft.net/{}", to_path(LAUNCHWRAPPER)),
+                None,
+            )),
+        }
+        let tweak = ["--tweakClass", TWEAK_CLASS];
+        Ok(Profile {
+            libraries,
+            minecraft_arguments: version
+                .minecraft_arguments
+                .as_ref()
+                .map(|x| format!("{x} {}", tweak.join(" "))),
+            arguments_game: version
+                .arguments
+                .as_ref()
+                .map(|_| tweak.iter().map(|&x| x.into()).collect()),
+        })
+    }
+
+    fn read(&mut self, name: &str) -> anyhow::Result<Vec<u8>> {
+        let mut file = self
+            .archive
+            .by_name(name)
+            .map_err(|_| anyhow::anyhow!("{} has no {name}", self.name))?;
+        let mut data = vec![];
+        file.read_to_end(&mut data)?;
+        Ok(data)
+    }
+}
+
+fn library(name: &str, url: String, sha1: Option<String>) -> official::Library {
+    official::Library {
+        downloads: Some(official::LibDownloads {
+            artifact: Some(official::Artifact {
+                path: to_path(name),
+                sha1,
+                size: None,
+                url,
+            }),
+            classifiers: None,
+        }),
+        name: name.to_owned(),
+        natives: None,
+        rules: None,
+        extract: None,
+    }
+}
+
+/// return the library `name` of `data`, which has no url as it is only
+/// shipped by the user, and write it into `libraries_dir`
+fn bundled_library(
+    name: &str,
+    data: &[u8],
+    libraries_dir: Option<&Path>,
+) -> anyhow::Result<official::Library> {
+    let library = library(name, String::new(), Some(hex::encode(Sha1::digest(data))));
+    if let Some(dir) = libraries_dir {
+        let file = dir.join(to_path(name));
+        fs::create_dir_all(file.parent().unwrap())?;
+        fs::write(file, data)?;
+    }
+    Ok(library)
+}
+
+impl official::MergeVersion for Profile {
+    fn official_libraries(&self) -> Option<Vec<official::Library>> {
+        Some(self.libraries.clone())
+    }
+    fn main_class(&self) -> Option<String> {
+        Some(LAUNCH_CLASS.into())
+    }
+    fn arguments_game(&self) -> Option<Vec<serde_json::Value>> {
+        self.arguments_game.clone()
+    }
+    fn arguments_jvm(&self) -> Option<Vec<serde_json::Value>> {
+        None
+    }
+    fn minecraft_arguments(&self) -> Option<String> {
+        self.minecraft_arguments.clone()
+    }
+}
+
+#[cfg(test)]
+fn zip_jar(files: &[(&str, &str)]) -> bytes::Bytes {
+    use std::io::Write;
+    let mut writer = zip::ZipWriter::new(Cursor::new(vec![]));
+    for (name, content) in files {
+        writer
+            .start_file(*name, zip::write::SimpleFileOptions::default())
+            .unwrap();
+        writer.write_all(content.as_bytes()).unwrap();
+    }
+    writer.finish().unwrap().into_inner().into()
+}
+
+/// the shared version fixture with the game arguments of before 1.13 if
+/// `legacy`, or of 1.13 and later
+#[cfg(test)]
+fn test_version(id: &str, legacy: bool) -> official::Version {
+    let mut version = official::test_version_json(id, serde_json::json!([]));
+    if legacy {
+        version["minecraftArguments"] = "--username ${auth_player_name}".into();
+    } else {
+        version["arguments"] = serde_json::json!({"game": ["--username"], "jvm": []});
+    }
+    serde_json::from_value(version).unwrap()
+}
+
+#[test]
+fn test_legacy_optifine() {
+    let data = zip_jar(&[("Config.class", "optifine")]);
+    let mut optifine = OptiFine::new("OptiFine_1.12.2_HD_U_G5.jar", data.clone()).unwrap();
+    assert_eq!(optifine.edition, "HD_U_G5");
+    let dir = crate::TestDir::new("legacy_optifine");
+    let mut version = test_version("1.12.2", true);
+    let profile = optifine.install(&version, Some(&dir)).unwrap();
+    let jar = dir.join("optifine/OptiFine/1.12.2_HD_U_G5/OptiFine-1.12.2_HD_U_G5.jar");
+    assert_eq!(fs::read(jar).unwrap(), data);
+    version.merge(profile);
+    assert_eq!(version.main_class, LAUNCH_CLASS);
+    assert_eq!(
+        version.minecraft_arguments.unwrap(),
+        "--username ${auth_player_name} --tweakClass optifine.OptiFineTweaker"
+    );
+    let launchwrapper = version.libraries[1].artifact().unwrap();
+    assert_eq!(
+        launchwrapper.url,
+        "https://libraries.minecraft.net/net/minecraft/launchwrapper/1.12/launchwrapper-1.12.jar"
+    );
+}
+
+#[test]
+fn test_optifine_launchwrapper_of() {
+    let data = zip_jar(&[
+        ("launchwrapper-of.txt", "2.1\n"),
+        ("launchwrapper-of-2.1.jar", "launchwrapper"),
+    ]);
+    let mut optifine = OptiFine::new("OptiFine_1.16.5_HD_U_G8_MOD.jar", data).unwrap();
+    let mut version = test_version("1.16.5", false);
+    version.merge(optifine.install(&version, None).unwrap());
+    let launchwrapper = version.libraries[1].artifact().unwrap();
+    assert_eq!(
+        launchwrapper.path,
+        "optifine/launchwrapper-of/2.1/launchwrapper-of-2.1.jar"
+    );
+    assert_eq!(
+        launchwrapper.sha1,
+        Some(hex::encode(Sha1::digest("launchwrapper")))
+    );
+    let game = &version.arguments.unwrap().game;
+    assert_eq!(game[1..], ["--tweakClass", TWEAK_CLASS]);
+}
+
+#[test]
+fn test_unsupported_optifine() {
+    let data = zip_jar(&[("Config.class", "optifine")]);
+    assert!(OptiFine::new("optifine.jar", data.clone()).is_err());
+    let mut optifine = OptiFine::new("OptiFine_1.12.2_HD_U_G5.jar", data.clone()).unwrap();
+    assert!(optifine.install(&test_version("1.12", true), None).is_err());
+    let mut optifine = OptiFine::new("OptiFine_1.16.5_HD_U_G8.jar", data).unwrap();
+    let version = test_version("1.16.5", false);
+    assert!(optifine.install(&version, None).is_err());
+    let patched = zip_jar(&[("patch/Config.class.xdelta", "")]);
+    assert!(OptiFine::new("OptiFine_1.12.2_HD_U_G5.jar", patched).is_err());
+}
@@ -0,0 +1,185 @@
+/// microsoft account login through the oauth device code flow
+/// https://learn.microsoft.com/entra/identity-platform/v2-oauth2-device-code
+use md5::{Digest, Md5};
+use serde::Deserialize;
+use serde_json::json;
+use std::{thread, time::Duration, time::Instant};
+
+const DEVICE_CODE_URL: &str = "https://login.microsoftonline.com/consumers/oauth2/v2.0/devicecode";
+const TOKEN_URL: &str = "https://login.microsoftonline.com/consumers/oauth2/v2.0/token";
+const XBL_URL: &str = "https://user.auth.xboxlive.com/user/authenticate";
+const XSTS_URL: &str = "https://xsts.auth.xboxlive.com/xsts/authorize";
+const LOGIN_URL: &str = "https://api.minecraftservices.com/authentication/login_with_xbox";
+const PROFILE_URL: &str = "https://api.minecraftservices.com/minecraft/profile";
+const SCOPE: &str = "XboxLive.signin offline_access";
+
+/// device code which the user enters at `verification_uri`
+#[derive(Debug, Deserialize)]
+pub struct DeviceCode {
+    pub device_code: String,
+    pub user_code: String,
+    pub verification_uri: String,
+    pub expires_in: u64,
+    pub interval: u64,
+    pub message: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct TokenResponse {
+    access_token: Option<String>,
+    error: Option<String>,
+    error_description: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct XboxResponse {
+    #[serde(rename = "Token")]
+    token: String,
+    #[serde(rename = "DisplayClaims")]
+    display_claims: serde_json::Value,
+}
+
+#[derive(Debug, Deserialize)]
+struct MinecraftToken {
+    access_token: String,
+}
+
+/// minecraft profile of a microsoft account
+#[derive(Debug, Deserialize, Clone)]
+pub struct Profile {
+    pub id: String,
+    pub name: String,
+}
+
+/// result of a microsoft account login
+#[derive(Debug, Clone)]
+pub struct MSAccount {
+    pub access_token: String,
+    pub profile: Profile,
+}
+
+impl DeviceCode {
+    /// request a device code for the azure application `client_id`
+    pub fn fetch(client_id: &str) -> anyhow::Result<Self> {
+        if client_id.is_empty() {
+            anyhow::bail!(
+                "msa_client_id is empty, set it to the client id of an azure application"
+            );
+        }
+        let client = crate::api::http_client();
+        let res = client
+            .post(DEVICE_CODE_URL)
+            .form(&[("client_id", client_id), ("scope", SCOPE)])
+            .send()?
+            .error_for_status()?;
+        Ok(res.json()?)
+    }
+
+    /// poll until the user finished the login in browser, then return the
+    /// microsoft access token
+    pub fn poll(&self, client_id: &str) -> anyhow::Result<String> {
+        let client = crate::api::http_client();
+        let deadline = Instant::now() + Duration::from_secs(self.expires_in);
+        let mut interval = self.interval;
+        while Instant::now() < deadline {
+            thread::sleep(Duration::from_secs(interval));
+            let res: TokenResponse = client
+                .post(TOKEN_URL)
+                .form(&[
+                    ("grant_type", "urn:ietf:params:oauth:grant-type:device_code"),
+                    ("client_id", client_id),
+                    ("device_code", &self.device_code),
+                ])
+                .send()?
+                .json()?;
+            if let Some(token) = res.access_token {
+                return Ok(token);
+            }
+            match res.error.as_deref() {
+                Some("authorization_pending") => {}
+                Some("slow_down") => interval += 5,
+                _ => anyhow::bail!(
+                    "microsoft login fail: {}",
+                    res.error_description.or(res.error).unwrap_or_default()
+                ),
+            }
+        }
+        anyhow::bail!("microsoft login timeout, the device code expired")
+    }
+}
+
+/// exchange a microsoft access token through xbox live and xsts for the
+/// minecraft access token and profile
+pub fn login(ms_token: &str) -> anyhow::Result<MSAccount> {
+    let client = crate::api::http_client();
+    let xbl: XboxResponse = client
+        .post(XBL_URL)
+        .json(&json!({
+            "Properties": {
+                "AuthMethod": "RPS",
+                "SiteName": "user.auth.xboxlive.com",
+                "RpsTicket": format!("d={ms_token}"),
+            },
+            "RelyingParty": "http://auth.xboxlive.com",
+            "TokenType": "JWT",
+        }))
+        .send()?
+        .error_for_status()?
+        .json()?;
+
+    let xsts = client
+        .post(XSTS_URL)
+        .json(&json!({
+            "Properties": {
+                "SandboxId": "RETAIL",
+                "UserTokens": [xbl.token],
+            },
+            "RelyingParty": "rp://api.minecraftservices.com/",
+            "TokenType": "JWT",
+        }))
+        .send()?;
+    if xsts.status() == reqwest::StatusCode::UNAUTHORIZED {
+        let err: serde_json::Value = xsts.json()?;
+        anyhow::bail!("xsts authorize fail, XErr {}", err["XErr"]);
+    }
+    let xsts: XboxResponse = xsts.error_for_status()?.json()?;
+    let uhs = xsts.display_claims["xui"][0]["uhs"]
+        .as_str()
+        .ok_or_else(|| anyhow::anyhow!("xsts response has no user hash"))?;
+
+    let mc: MinecraftToken = client
+        .post(LOGIN_URL)
+        .json(&json!({ "identityToken": format!("XBL3.0 x={uhs};{}", xsts.token) }))
+        .send()?
+        .error_for_status()?
+        .json()?;
+    let profile = client
+        .get(PROFILE_URL)
+        .bearer_auth(&mc.access_token)
+        .send()?;
+    if profile.status() == reqwest::StatusCode::NOT_FOUND {
+        anyhow::bail!("this microsoft account does not own minecraft");
+    }
+    Ok(MSAccount {
+        access_token: mc.access_token,
+        profile: profile.error_for_status()?.json()?,
+    })
+}
+
+/// return the uuid vanilla derives for an offline player, which is the name
+/// based (version 3) uuid of `OfflinePlayer:<name>`
+pub fn offline_uuid(name: &str) -> String {
+    let hash = Md5::digest(format!("OfflinePlayer:{name}"));
+    uuid::Builder::from_md5_bytes(hash.into())
+        .into_uuid()
+        .hyphenated()
+        .to_string()
+}
+
+#[test]
+fn test_offline_uuid() {
+    assert_eq!(
+        offline_uuid("Notch"),
+        "b50ad385-829d-3141-a216-7e7d7539ba7f"
+    );
+}
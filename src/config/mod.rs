@@ -1,9 +1,66 @@
-use clap::Subcommand;
+use crate::api::official::VersionManifest;
+use clap::ValueEnum;
 use serde::{Deserialize, Serialize};
+use std::{
+    collections::BTreeMap,
+    ffi::OsString,
+    path::{Path, PathBuf},
+    time::Duration,
+};
 use uuid::Uuid;
 
+/// resolve the base directory with `var` reading environment variables:
+/// `$MCLAUNCHER_HOME`, then `$XDG_DATA_HOME/mclauncher`, then
+/// `~/.local/share/mclauncher` (`%APPDATA%\mclauncher` on windows)
+fn base_dir_from<F>(var: F) -> Option<PathBuf>
+where
+    F: Fn(&str) -> Option<OsString>,
+{
+    let non_empty = |name| var(name).filter(|x: &OsString| !x.is_empty());
+    if let Some(home) = non_empty("MCLAUNCHER_HOME") {
+        return Some(home.into());
+    }
+    if let Some(data) = non_empty("XDG_DATA_HOME") {
+        return Some(PathBuf::from(data).join("mclauncher"));
+    }
+    if cfg!(target_os = "windows") {
+        return non_empty("APPDATA").map(|x| PathBuf::from(x).join("mclauncher"));
+    }
+    non_empty("HOME").map(|x| PathBuf::from(x).join(".local/share/mclauncher"))
+}
+
+/// return the directory holding config.toml, versions, assets and
+/// libraries, the current directory if no home directory is found
+pub fn base_dir() -> PathBuf {
+    base_dir_from(|name| std::env::var_os(name)).unwrap_or_else(|| std::env::current_dir().unwrap())
+}
+
+#[test]
+fn test_base_dir() {
+    let env = |vars: &'static [(&'static str, &'static str)]| {
+        move |name: &str| {
+            vars.iter()
+                .find(|x| x.0 == name)
+                .map(|x| OsString::from(x.1))
+        }
+    };
+    let dir = base_dir_from(env(&[
+        ("MCLAUNCHER_HOME", "/mc"),
+        ("XDG_DATA_HOME", "/data"),
+    ]));
+    assert_eq!(dir, Some(PathBuf::from("/mc")));
+    let dir = base_dir_from(env(&[("XDG_DATA_HOME", "/data"), ("HOME", "/home/a")]));
+    assert_eq!(dir, Some(PathBuf::from("/data/mclauncher")));
+    if !cfg!(target_os = "windows") {
+        let dir = base_dir_from(env(&[("XDG_DATA_HOME", ""), ("HOME", "/home/a")]));
+        assert_eq!(dir, Some(PathBuf::from("/home/a/.local/share/mclauncher")));
+    }
+    assert_eq!(base_dir_from(env(&[])), None);
+}
+
 // runtime config
-#[derive(Debug, Serialize, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(default)]
 pub struct MCMirror {
     pub version_manifest: String,
     pub assets: String,
@@ -11,6 +68,14 @@ pub struct MCMirror {
     pub libraries: String,
     pub fabric_meta: String,
     pub fabric_maven: String,
+    pub forge_maven: String,
+    pub neoforge_maven: String,
+}
+
+impl Default for MCMirror {
+    fn default() -> Self {
+        MCMirror::official_mirror()
+    }
 }
 
 impl MCMirror {
@@ -22,6 +87,8 @@ impl MCMirror {
             libraries: "https://libraries.minecraft.net/".into(),
             fabric_meta: "https://meta.fabricmc.net/".into(),
             fabric_maven: "https://maven.fabricmc.net/".into(),
+            forge_maven: "https://maven.minecraftforge.net/".into(),
+            neoforge_maven: "https://maven.neoforged.net/releases/".into(),
         }
     }
     pub fn bmcl_mirror() -> Self {
@@ -32,58 +99,606 @@ impl MCMirror {
             libraries: "https://bmclapi2.bangbang93.com/maven/".into(),
             fabric_meta: "https://bmclapi2.bangbang93.com/fabric-meta/".into(),
             fabric_maven: "https://bmclapi2.bangbang93.com/maven/".into(),
+            forge_maven: "https://bmclapi2.bangbang93.com/maven/".into(),
+            neoforge_maven: "https://bmclapi2.bangbang93.com/maven/".into(),
+        }
+    }
+    /// check every url of the mirror is a https url ending with `/`
+    /// # Error
+    /// Return Error listing every url which is not
+    pub fn validate(&self) -> anyhow::Result<()> {
+        let problems = self.problems();
+        if !problems.is_empty() {
+            anyhow::bail!("mirror is invalid:\n  - {}", problems.join("\n  - "));
+        }
+        Ok(())
+    }
+    /// return a problem for every url of the mirror which is not a https url
+    /// ending with `/`
+    pub fn problems(&self) -> Vec<String> {
+        let urls = [
+            ("version_manifest", &self.version_manifest),
+            ("assets", &self.assets),
+            ("client", &self.client),
+            ("libraries", &self.libraries),
+            ("fabric_meta", &self.fabric_meta),
+            ("fabric_maven", &self.fabric_maven),
+            ("forge_maven", &self.forge_maven),
+            ("neoforge_maven", &self.neoforge_maven),
+        ];
+        let mut problems = vec![];
+        for (name, url) in urls {
+            match reqwest::Url::parse(url) {
+                Err(e) => problems.push(format!("mirror {name} {url} is not a url: {e}")),
+                Ok(parsed) if parsed.scheme() != "https" || !url.ends_with('/') => {
+                    problems.push(format!(
+                        "mirror {name} {url} must be a https url ending with `/`"
+                    ));
+                }
+                Ok(_) => {}
+            }
+        }
+        problems
+    }
+    /// return the mirror to retry a failed download on: bmclapi for the
+    /// official mirror, and the official mirror for any other
+    pub fn fallback_mirror(&self) -> Self {
+        if *self == MCMirror::official_mirror() {
+            MCMirror::bmcl_mirror()
+        } else {
+            MCMirror::official_mirror()
         }
     }
 }
 
-#[derive(Debug,Serialize,Deserialize,Clone)]
+#[test]
+fn test_mirror_validate() {
+    assert!(MCMirror::official_mirror().validate().is_ok());
+    assert!(MCMirror::bmcl_mirror().validate().is_ok());
+    let mirror = MCMirror {
+        assets: "http://127.0.0.1/assets/".into(),
+        ..MCMirror::official_mirror()
+    };
+    assert!(mirror.validate().is_err());
+    let mirror = MCMirror {
+        client: "https://127.0.0.1/client".into(),
+        ..MCMirror::official_mirror()
+    };
+    assert!(mirror.validate().is_err());
+    let mirror = MCMirror {
+        assets: "http://127.0.0.1/assets/".into(),
+        client: "not a url".into(),
+        ..MCMirror::official_mirror()
+    };
+    let problems = mirror.validate().unwrap_err().to_string();
+    assert!(problems.contains("mirror assets"));
+    assert!(problems.contains("mirror client"));
+}
+
+#[test]
+fn test_profiles() {
+    let mut config = RuntimeConfig {
+        game_dir: "/games/default".into(),
+        game_version: "1.20.4".into(),
+        ..Default::default()
+    };
+    config.select_account(Account {
+        user_name: "Steve".into(),
+        user_type: "offline".into(),
+        user_uuid: String::new(),
+        access_token: String::new(),
+    });
+    config
+        .create_profile("modpack", "/games/modpack".into())
+        .unwrap();
+    assert!(config.create_profile("modpack", "/x".into()).is_err());
+    config.use_profile("modpack").unwrap();
+    assert_eq!(config.game_dir, PathBuf::from("/games/modpack"));
+    config.game_version = "1.12.2".into();
+    config.loader = MCLoader::Forge("recommended".into());
+    config
+        .create_profile("vanilla", "/games/default".into())
+        .unwrap();
+    config.use_profile("vanilla").unwrap();
+    assert_eq!(config.game_version, "1.12.2");
+    config.game_version = "1.20.4".into();
+    config.loader = MCLoader::None;
+    config.use_profile("modpack").unwrap();
+    assert_eq!(config.game_version, "1.12.2");
+    assert_eq!(config.loader, MCLoader::Forge("recommended".into()));
+    assert_eq!(config.profiles["vanilla"].game_version, "1.20.4");
+    assert!(config.use_profile("missing").is_err());
+}
+
+#[test]
+fn test_version_override() {
+    let config: RuntimeConfig = toml::from_str(
+        r#"
+        game_version = "1.8.9"
+        java_path = "java"
+        max_memory_size = 4096
+        jvm_args = ["-XX:+UseZGC"]
+        [versions."1.8.9"]
+        java_path = "/usr/lib/jvm/java-8/bin/java"
+        max_memory_size = 2048
+        main_class = "net.minecraft.launchwrapper.Launch"
+        "#,
+    )
+    .unwrap();
+    let other = RuntimeConfig {
+        game_version: "1.20.4".into(),
+        ..config.clone()
+    };
+    let other = other.with_version_override();
+    assert_eq!(other.max_memory_size, 4096);
+    assert_eq!(other.main_class, None);
+    let config = config.with_version_override();
+    assert_eq!(config.java_path, "/usr/lib/jvm/java-8/bin/java");
+    assert_eq!(config.max_memory_size, 2048);
+    assert_eq!(config.jvm_args, ["-XX:+UseZGC"]);
+    assert_eq!(
+        config.main_class.as_deref(),
+        Some("net.minecraft.launchwrapper.Launch")
+    );
+}
+
+#[test]
+fn test_game_dirs() {
+    let config: RuntimeConfig = toml::from_str(
+        r#"
+        game_dir = "/games/mclauncher"
+        assets_dir = "/home/steve/.minecraft/assets"
+        libraries_dir = "shared/libraries"
+        "#,
+    )
+    .unwrap();
+    let shared = PathBuf::from("/home/steve/.minecraft/assets");
+    assert_eq!(config.assets_dir(), shared);
+    assert_eq!(config.assets_dir_in(Path::new("/games/other")), shared);
+    assert_eq!(
+        config.libraries_dir(),
+        PathBuf::from("/games/mclauncher/shared/libraries")
+    );
+    assert_eq!(
+        config.versions_dir(),
+        PathBuf::from("/games/mclauncher/versions")
+    );
+}
+
+#[test]
+fn test_config_validate() {
+    let config = RuntimeConfig {
+        max_memory_size: 0,
+        max_concurrent_downloads: 0,
+        java_path: "/nonexistent/java".into(),
+        game_dir: "/nonexistent".into(),
+        proxy: Some("not a url".into()),
+        user_agent: "MCLauncher\n".into(),
+        env: [("A=B".into(), "1".into())].into(),
+        auth_server: Some("https://littleskin.cn/api/yggdrasil".into()),
+        mirror: MCMirror {
+            assets: "http://127.0.0.1/assets/".into(),
+            client: "http://127.0.0.1/client/".into(),
+            ..MCMirror::official_mirror()
+        },
+        ..Default::default()
+    };
+    let problems = config.validate().unwrap_err().to_string();
+    assert!(problems.contains("mirror assets"));
+    assert!(problems.contains("mirror client"));
+    assert!(problems.contains("user_agent"));
+    assert!(problems.contains("proxy"));
+    assert!(problems.contains("max_memory_size"));
+    assert!(problems.contains("java_path"));
+    assert!(problems.contains("game_dir"));
+    assert!(problems.contains("max_concurrent_downloads"));
+    assert!(problems.contains("env \"A=B\""));
+    assert!(problems.contains("authlib_injector and auth_server"));
+    assert!(!problems.contains("window"));
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
 pub enum MCLoader {
     None,
     Fabric(String),
+    /// forge build, or `recommended` / `latest`
+    Forge(String),
+    /// neoforge build, or `latest`
+    NeoForge(String),
+    /// path or url of an optifine jar, installed standalone
+    OptiFine(String),
+}
+
+/// a saved account, the selected one is copied into the `user_*` fields of
+/// RuntimeConfig
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct Account {
+    pub user_name: String,
+    pub user_type: String,
+    pub user_uuid: String,
+    pub access_token: String,
+}
+
+/// an isolated instance with its own game_dir, version and account, the
+/// active one is copied into the fields of RuntimeConfig
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct Instance {
+    pub game_dir: PathBuf,
+    pub game_version: String,
+    pub loader: MCLoader,
+    /// user name of the saved account the instance plays with
+    pub account: String,
+}
+
+/// a `[versions.<id>]` table, the set fields replace the global ones when
+/// that version is launched
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq)]
+#[serde(default)]
+pub struct VersionOverride {
+    pub java_path: Option<String>,
+    pub max_memory_size: Option<u32>,
+    pub jvm_args: Option<Vec<String>>,
+    pub main_class: Option<String>,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(default)]
 pub struct RuntimeConfig {
+    /// initial heap size in MiB
+    pub min_memory_size: u32,
     pub max_memory_size: u32,
     pub window_weight: u32,
     pub window_height: u32,
     pub user_name: String,
     pub user_type: String,
     pub user_uuid: String,
-    pub game_dir: String,
+    /// minecraft access token of a microsoft account, empty when offline
+    pub access_token: String,
+    /// client id of the azure application used for microsoft login
+    pub msa_client_id: String,
+    pub game_dir: PathBuf,
+    /// directory of the asset indexes and objects, relative to game_dir
+    /// unless absolute, such as the `assets` of the official launcher to
+    /// share them, `assets` if unset
+    pub assets_dir: Option<PathBuf>,
+    /// directory of the libraries like `assets_dir`, `libraries` if unset
+    pub libraries_dir: Option<PathBuf>,
+    /// directory of the version jsons, clients and natives like
+    /// `assets_dir`, `versions` if unset
+    pub versions_dir: Option<PathBuf>,
     pub game_version: String,
     pub java_path: String,
     pub loader: MCLoader,
     pub mirror: MCMirror,
+    /// number of worker threads used to download files, 1 downloads them
+    /// one after another
+    pub max_concurrent_downloads: usize,
+    /// number of attempts of every download
+    pub download_retries: usize,
+    /// timeout of every download request in seconds
+    pub download_timeout_secs: u64,
+    /// soft limit of the total download rate in KB/s, 0 for no limit
+    pub max_bandwidth: u64,
+    /// seconds the cached version manifest is used before it is fetched again
+    pub manifest_ttl_secs: u64,
+    /// user agent of every request
+    pub user_agent: String,
+    /// proxy url of every request such as `http://127.0.0.1:7890`, without
+    /// it the `HTTPS_PROXY` and `HTTP_PROXY` environment variables are used
+    pub proxy: Option<String>,
+    /// hard link asset files into legacy asset directories and from other
+    /// profiles instead of copying them, copying is the fallback
+    pub use_hardlinks: bool,
+    /// saved accounts keyed by user name
+    pub accounts: BTreeMap<String, Account>,
+    /// name of the active profile
+    pub profile: Option<String>,
+    /// instances keyed by profile name
+    pub profiles: BTreeMap<String, Instance>,
+    /// extra jvm arguments, such as garbage collector flags
+    pub jvm_args: Vec<String>,
+    /// main class launched instead of the `mainClass` of the version json,
+    /// such as a tweaked launchwrapper of a modpack
+    pub main_class: Option<String>,
+    /// environment variables of the game process, such as
+    /// `__GL_THREADED_OPTIMIZATIONS` or `MESA_GL_VERSION_OVERRIDE`
+    pub env: BTreeMap<String, String>,
+    /// command run in game_dir before the game, as the program and its
+    /// arguments, the launch is aborted if it fails
+    pub pre_launch: Vec<String>,
+    /// command run in game_dir after the game exited
+    pub post_launch: Vec<String>,
+    /// authlib-injector jar, relative to game_dir unless absolute, loaded as
+    /// java agent to log in on `auth_server` instead of the mojang servers
+    pub authlib_injector: Option<PathBuf>,
+    /// yggdrasil api url of an authlib-injector server such as
+    /// `https://littleskin.cn/api/yggdrasil`
+    pub auth_server: Option<String>,
+    /// overrides of single versions keyed by version id
+    pub versions: BTreeMap<String, VersionOverride>,
 }
 
 impl Default for RuntimeConfig {
     fn default() -> Self {
         RuntimeConfig {
+            min_memory_size: 1024,
             max_memory_size: 5000,
             window_weight: 854,
             window_height: 480,
             user_name: "no_name".into(),
             user_type: "offline".into(),
             user_uuid: Uuid::new_v4().into(),
-            game_dir: std::env::current_dir()
-                .unwrap()
-                .to_str()
-                .unwrap()
-                .to_owned()
-                + "/",
+            access_token: String::new(),
+            msa_client_id: String::new(),
+            game_dir: base_dir(),
+            assets_dir: None,
+            libraries_dir: None,
+            versions_dir: None,
             game_version: "no_game_version".into(),
             java_path: "java".into(),
             mirror: MCMirror::official_mirror(),
             loader: MCLoader::None,
+            max_concurrent_downloads: 8,
+            download_retries: 5,
+            download_timeout_secs: 60,
+            max_bandwidth: 0,
+            manifest_ttl_secs: 3600,
+            user_agent: crate::api::DEFAULT_USER_AGENT.into(),
+            proxy: None,
+            use_hardlinks: true,
+            accounts: BTreeMap::new(),
+            profile: None,
+            profiles: BTreeMap::new(),
+            jvm_args: vec![],
+            main_class: None,
+            env: BTreeMap::new(),
+            pre_launch: vec![],
+            post_launch: vec![],
+            authlib_injector: None,
+            auth_server: None,
+            versions: BTreeMap::new(),
+        }
+    }
+}
+
+impl RuntimeConfig {
+    /// return the selected account
+    pub fn account(&self) -> Account {
+        Account {
+            user_name: self.user_name.clone(),
+            user_type: self.user_type.clone(),
+            user_uuid: self.user_uuid.clone(),
+            access_token: self.access_token.clone(),
+        }
+    }
+
+    /// save `account` under its user name and select it
+    pub fn select_account(&mut self, account: Account) {
+        self.user_name = account.user_name.clone();
+        self.user_type = account.user_type.clone();
+        self.user_uuid = account.user_uuid.clone();
+        self.access_token = account.access_token.clone();
+        self.accounts.insert(account.user_name.clone(), account);
+    }
+
+    /// return the current game_dir, version and account as an instance
+    fn instance(&self) -> Instance {
+        Instance {
+            game_dir: self.game_dir.clone(),
+            game_version: self.game_version.clone(),
+            loader: self.loader.clone(),
+            account: self.user_name.clone(),
+        }
+    }
+
+    /// copy the current game_dir, version and account into the active profile
+    pub fn save_profile(&mut self) {
+        if let Some(name) = &self.profile {
+            self.profiles.insert(name.clone(), self.instance());
+        }
+    }
+
+    /// add profile `name` in `game_dir` with the current version and account
+    pub fn create_profile(&mut self, name: &str, game_dir: PathBuf) -> anyhow::Result<()> {
+        if self.profiles.contains_key(name) {
+            anyhow::bail!("profile {name} already exists");
+        }
+        let instance = Instance {
+            game_dir,
+            ..self.instance()
+        };
+        self.profiles.insert(name.to_owned(), instance);
+        Ok(())
+    }
+
+    /// save the active profile and switch to profile `name`
+    pub fn use_profile(&mut self, name: &str) -> anyhow::Result<()> {
+        let instance = self
+            .profiles
+            .get(name)
+            .cloned()
+            .ok_or_else(|| anyhow::anyhow!("profile {name} not found"))?;
+        self.save_profile();
+        self.game_dir = instance.game_dir;
+        self.game_version = instance.game_version;
+        self.loader = instance.loader;
+        match self.accounts.get(&instance.account).cloned() {
+            Some(account) => self.select_account(account),
+            None => log::warn!(
+                "account {} of profile {name} is not saved, keep {}",
+                instance.account,
+                self.user_name
+            ),
+        }
+        self.profile = Some(name.to_owned());
+        Ok(())
+    }
+
+    /// check the config can be used to install and launch the game
+    /// # Error
+    /// Return Error listing every problem found
+    pub fn validate(&self) -> anyhow::Result<()> {
+        let mut problems = vec![];
+        if self.min_memory_size == 0 || self.max_memory_size == 0 {
+            problems.push("min_memory_size and max_memory_size must be positive".to_owned());
+        }
+        if self.window_weight == 0 || self.window_height == 0 {
+            problems.push("window_weight and window_height must be positive".to_owned());
+        }
+        if self.max_concurrent_downloads == 0 {
+            problems.push("max_concurrent_downloads must be at least 1".to_owned());
+        } else if self.max_concurrent_downloads > 128 {
+            log::warn!(
+                "max_concurrent_downloads is {}, mirrors may reject that many connections",
+                self.max_concurrent_downloads
+            );
+        }
+        if crate::java::resolve_java(&self.java_path).is_none() {
+            problems.push(format!(
+                "java_path {} is not an executable file",
+                self.java_path
+            ));
+        }
+        for (id, version) in &self.versions {
+            if let Some(java_path) = &version.java_path {
+                if crate::java::resolve_java(java_path).is_none() {
+                    problems.push(format!(
+                        "versions.{id}.java_path {java_path} is not an executable file"
+                    ));
+                }
+            }
+        }
+        if !self.game_dir.is_dir() {
+            problems.push(format!(
+                "game_dir {} is not a directory",
+                self.game_dir.display()
+            ));
+        }
+        problems.extend(self.mirror.problems());
+        if self.user_agent.is_empty()
+            || reqwest::header::HeaderValue::from_str(&self.user_agent).is_err()
+        {
+            problems.push(format!(
+                "user_agent {:?} is not a valid header value",
+                self.user_agent
+            ));
+        }
+        if let Some(proxy) = &self.proxy {
+            if let Err(e) = reqwest::Proxy::all(proxy) {
+                problems.push(format!("proxy {proxy} is not a url: {e}"));
+            }
+        }
+        for (name, value) in &self.env {
+            if name.is_empty() || name.contains(['=', '\0']) || value.contains('\0') {
+                problems.push(format!("env {name:?} is not a valid environment variable"));
+            }
+        }
+        match (&self.authlib_injector, &self.auth_server) {
+            (Some(_), Some(url)) => {
+                if !reqwest::Url::parse(url).is_ok_and(|x| matches!(x.scheme(), "http" | "https")) {
+                    problems.push(format!("auth_server {url} is not a http(s) url"));
+                }
+            }
+            (None, None) => {}
+            _ => problems.push("authlib_injector and auth_server must be set together".to_owned()),
+        }
+        if !problems.is_empty() {
+            anyhow::bail!("config is invalid:\n  - {}", problems.join("\n  - "));
+        }
+        Ok(())
+    }
+
+    /// return the config with the `[versions.<id>]` override of
+    /// `game_version` applied, unset fields keep the global values
+    pub fn with_version_override(mut self) -> Self {
+        let Some(version) = self.versions.get(&self.game_version).cloned() else {
+            return self;
+        };
+        if let Some(java_path) = version.java_path {
+            self.java_path = java_path;
+        }
+        if let Some(max_memory_size) = version.max_memory_size {
+            self.max_memory_size = max_memory_size;
+        }
+        if let Some(jvm_args) = version.jvm_args {
+            self.jvm_args = jvm_args;
+        }
+        if let Some(main_class) = version.main_class {
+            self.main_class = Some(main_class);
+        }
+        self
+    }
+
+    /// return the version manifest of the mirror, cached in
+    /// `game_dir/manifest.json` for `manifest_ttl_secs`, `refresh` always
+    /// fetches it
+    pub fn version_manifest(&self, refresh: bool) -> anyhow::Result<VersionManifest> {
+        VersionManifest::fetch_cached(
+            &self.mirror.version_manifest,
+            &self.game_dir.join("manifest.json"),
+            Duration::from_secs(self.manifest_ttl_secs),
+            refresh,
+        )
+    }
+
+    /// return the assets directory, `assets_dir` in game_dir
+    pub fn assets_dir(&self) -> PathBuf {
+        self.assets_dir_in(&self.game_dir)
+    }
+
+    /// return the assets directory in `game_dir` of another profile, which
+    /// is the shared one if `assets_dir` is absolute
+    pub fn assets_dir_in(&self, game_dir: &Path) -> PathBuf {
+        game_dir.join(self.assets_dir.as_deref().unwrap_or(Path::new("assets")))
+    }
+
+    /// return the libraries directory, `libraries_dir` in game_dir
+    pub fn libraries_dir(&self) -> PathBuf {
+        let dir = self.libraries_dir.as_deref();
+        self.game_dir.join(dir.unwrap_or(Path::new("libraries")))
+    }
+
+    /// return the versions directory, `versions_dir` in game_dir
+    pub fn versions_dir(&self) -> PathBuf {
+        let dir = self.versions_dir.as_deref();
+        self.game_dir.join(dir.unwrap_or(Path::new("versions")))
+    }
+
+    /// return the directory old versions read assets from by their original
+    /// path: `assets/virtual/legacy` for the `legacy` asset index (1.6) and
+    /// `resources` for `pre-1.6`, newer asset indexes have none
+    pub fn legacy_assets_dir(&self, asset_index_id: &str) -> Option<PathBuf> {
+        match asset_index_id {
+            "legacy" => Some(self.assets_dir().join("virtual").join("legacy")),
+            "pre-1.6" => Some(self.game_dir.join("resources")),
+            _ => None,
         }
     }
 }
 
 // version type
-#[derive(Subcommand, Debug)]
+#[derive(ValueEnum, Debug, Clone, Copy)]
 pub enum VersionType {
     All,
     Release,
     Snapshot,
+    /// beta 1.0 to 1.8
+    #[value(alias = "old_beta")]
+    OldBeta,
+    /// classic, indev, infdev and alpha
+    #[value(alias = "old_alpha")]
+    OldAlpha,
+}
+
+impl VersionType {
+    /// return the `type` of the version manifest, `None` for all types
+    pub fn manifest_type(&self) -> Option<&'static str> {
+        match self {
+            VersionType::All => None,
+            VersionType::Release => Some("release"),
+            VersionType::Snapshot => Some("snapshot"),
+            VersionType::OldBeta => Some("old_beta"),
+            VersionType::OldAlpha => Some("old_alpha"),
+        }
+    }
 }
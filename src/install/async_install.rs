@@ -0,0 +1,265 @@
+/// install with the async reqwest client on a tokio runtime, for embedders
+/// which already run one, the metadata steps run on the blocking pool
+use super::{
+    backoff, check_failed, finish_install, finish_part, is_interrupted, part_file, prepare_install,
+    progress::ProgressReporter, start_part, total_bytes, write_download_manifest, Downloader,
+    InstallOptions, InstallReport, InstallTask, Interrupted, PartStart, Prepared,
+};
+use crate::config::RuntimeConfig;
+use log::warn;
+use reqwest::header;
+#[cfg(test)]
+use sha1::{Digest, Sha1};
+use std::{path::Path, sync::Arc, time::Instant};
+use tokio::{io::AsyncWriteExt, sync::Semaphore, task::JoinSet};
+
+/// download `url` into `part` and return the sha1 of `part`, resuming from
+/// its current length like `Downloader::download_part`, the existing data
+/// is hashed on the blocking pool
+async fn download_part(
+    client: &reqwest::Client,
+    downloader: &Downloader,
+    url: &str,
+    part: &Path,
+    size: Option<u64>,
+) -> anyhow::Result<String> {
+    let offset = tokio::fs::metadata(part).await.map_or(0, |x| x.len());
+    let mut request = client.get(url).timeout(downloader.timeout);
+    if offset > 0 {
+        request = request.header(header::RANGE, format!("bytes={offset}-"));
+    }
+    let mut response = request.send().await?;
+    let start = {
+        let (url, part, status) = (url.to_owned(), part.to_owned(), response.status());
+        tokio::task::spawn_blocking(move || start_part(&url, &part, offset, size, status)).await??
+    };
+    let (mut file, mut hasher) = match start {
+        PartStart::Complete(hash) => return Ok(hash),
+        PartStart::Append(hasher) => {
+            let file = tokio::fs::OpenOptions::new()
+                .append(true)
+                .open(part)
+                .await?;
+            (file, hasher)
+        }
+        PartStart::Create(hasher) => (tokio::fs::File::create(part).await?, hasher),
+    };
+    hasher.check_length(response.content_length())?;
+    while let Some(chunk) = response.chunk().await? {
+        if is_interrupted() {
+            anyhow::bail!("interrupted");
+        }
+        if let Some(bandwidth) = &downloader.bandwidth {
+            tokio::time::sleep(bandwidth.reserve(chunk.len(), Instant::now())).await;
+        }
+        hasher.update(&chunk);
+        file.write_all(&chunk).await?;
+    }
+    file.flush().await?;
+    hasher.finish()
+}
+
+/// download `url` to the save file of `task` through a `.part` file with the
+/// retries and backoff of `downloader`, the part is renamed once its size
+/// and sha1 match and kept for resuming otherwise
+async fn download(
+    client: &reqwest::Client,
+    downloader: &Downloader,
+    url: &str,
+    task: &InstallTask,
+) -> anyhow::Result<()> {
+    let part = part_file(&task.save_file);
+    tokio::fs::create_dir_all(task.save_file.parent().unwrap()).await?;
+    for attempt in 0..downloader.retries {
+        if is_interrupted() {
+            anyhow::bail!("interrupted");
+        }
+        if attempt > 0 {
+            warn!(
+                "retry {url}, attempt {}/{}",
+                attempt + 1,
+                downloader.retries
+            );
+            tokio::time::sleep(backoff(attempt - 1)).await;
+        }
+        match download_part(client, downloader, url, &part, task.size).await {
+            Ok(hash) => {
+                if finish_part(task, &part, &hash)? {
+                    return Ok(());
+                }
+            }
+            Err(e) => warn!("download {url} fail: {e}"),
+        }
+    }
+    Err(anyhow::anyhow!("download {url} fail"))
+}
+
+/// install `task` like `FileInstall::install`, the cache check hashes the
+/// save file on the blocking pool
+async fn install_task(
+    client: reqwest::Client,
+    downloader: Arc<Downloader>,
+    task: InstallTask,
+    options: Arc<InstallOptions>,
+    reporter: Arc<dyn ProgressReporter + Send>,
+) -> anyhow::Result<InstallReport> {
+    let name = task.name();
+    let fail = |e: anyhow::Error| {
+        let e = anyhow::anyhow!("{name}: {e}");
+        reporter.on_file_failed(&name, &e.to_string());
+        e
+    };
+    if let Err(e) = task.check_verifiable(&options) {
+        reporter.on_file_failed(&name, &e.to_string());
+        return Err(e);
+    }
+    let cached = !options.force && {
+        let task = task.clone();
+        tokio::task::spawn_blocking(move || task.is_cached()).await?
+    };
+    let mut served = None;
+    if !cached {
+        let mut res = download(&client, &downloader, &task.url, &task)
+            .await
+            .map(|_| (&task.url, false));
+        if let (Err(e), Some(url)) = (&res, &task.fallback_url) {
+            warn!("{e}, fall back to {url}");
+            res = download(&client, &downloader, url, &task)
+                .await
+                .map(|_| (url, true));
+            if res.is_ok() {
+                warn!("{url} served by the fallback mirror");
+            }
+        }
+        served = Some(res.map_err(fail)?);
+    }
+    let bytes = tokio::fs::metadata(&task.save_file)
+        .await
+        .map_or(0, |x| x.len());
+    reporter.on_file_done(&name, bytes, cached);
+    let mut report = task.report(bytes, cached);
+    if let Some((url, fallback)) = served {
+        report.downloads.push(task.record(url, fallback));
+    }
+    Ok(report)
+}
+
+/// async variant of `install_mc`, at most `max_concurrent_downloads` files
+/// are downloaded at once
+/// # Error
+/// Return one Error listing the failed files like `install_mc`, or only warn
+/// about them with `continue_on_error`
+pub async fn install_mc_async(
+    config: RuntimeConfig,
+    options: InstallOptions,
+    reporter: Arc<dyn ProgressReporter + Send>,
+) -> anyhow::Result<InstallReport> {
+    let start = Instant::now();
+    crate::api::ensure_http_client(config.proxy.as_deref(), &config.user_agent)?;
+    let config = Arc::new(config);
+    let options = Arc::new(options);
+    let prepared = {
+        let (config, options, reporter) = (config.clone(), options.clone(), reporter.clone());
+        tokio::task::spawn_blocking(move || -> anyhow::Result<_> {
+            let prepared = prepare_install(&config, &options, &*reporter)?;
+            Ok((prepared, Downloader::new(&config)?))
+        })
+        .await??
+    };
+    let (version, assets, tasks, skipped, downloader) = match prepared {
+        (Prepared::Installed(mut report), _) => {
+            report.duration = start.elapsed();
+            return Ok(report);
+        }
+        (
+            Prepared::Download {
+                version,
+                assets,
+                tasks,
+                skipped,
+            },
+            downloader,
+        ) => (version, assets, tasks, skipped, Arc::new(downloader)),
+    };
+
+    let client = crate::api::async_http_client()?;
+    let semaphore = Arc::new(Semaphore::new(config.max_concurrent_downloads.max(1)));
+    let total = tasks.len();
+    reporter.on_start(total as u64);
+    if let Some(bytes) = total_bytes(&tasks) {
+        reporter.on_total_bytes(bytes);
+    }
+    let mut set = JoinSet::new();
+    // a permit is taken before spawning, so at most that many tasks exist
+    for task in tasks {
+        let permit = semaphore.clone().acquire_owned().await?;
+        // the started tasks finish or stop at their next chunk
+        if is_interrupted() {
+            break;
+        }
+        let install = install_task(
+            client.clone(),
+            downloader.clone(),
+            task,
+            options.clone(),
+            reporter.clone(),
+        );
+        set.spawn(async move {
+            let _permit = permit;
+            install.await
+        });
+    }
+    let mut report = InstallReport::default();
+    let mut installed = 0;
+    let mut failed = vec![];
+    while let Some(res) = set.join_next().await {
+        match res? {
+            Ok(x) => {
+                report += x;
+                installed += 1;
+            }
+            Err(e) => failed.push(e.to_string()),
+        }
+    }
+    reporter.on_finish();
+    if is_interrupted() {
+        return Err(Interrupted { installed, total }.into());
+    }
+    check_failed(failed, total, &options, &mut report)?;
+    report += skipped;
+
+    let mut report = tokio::task::spawn_blocking(move || -> anyhow::Result<_> {
+        finish_install(&config, &version, &assets, &*reporter)?;
+        if options.download_manifest && !report.downloads.is_empty() {
+            write_download_manifest(&config, &report.downloads)?;
+        }
+        Ok(report)
+    })
+    .await??;
+    report.duration = start.elapsed();
+    Ok(report)
+}
+
+#[test]
+fn test_install_task_async() {
+    let dir = crate::TestDir::new("install_task_async");
+    let task = InstallTask {
+        sha1: Some(hex::encode(Sha1::digest("asset"))),
+        save_file: dir.join("asset"),
+        ..Default::default()
+    };
+    std::fs::write(&task.save_file, "asset").unwrap();
+    let downloader = Downloader::new(&RuntimeConfig::default()).unwrap();
+    let client = crate::api::async_http_client().unwrap();
+    let runtime = tokio::runtime::Runtime::new().unwrap();
+    let report = runtime
+        .block_on(install_task(
+            client,
+            Arc::new(downloader),
+            task,
+            Arc::new(InstallOptions::default()),
+            Arc::new(super::progress::SilentReporter),
+        ))
+        .unwrap();
+    assert_eq!(report.assets_skipped, 1);
+}
@@ -1,66 +1,244 @@
 use crate::{
-    api::official::{Assets, Version, VersionManifest},
     api::fabric::Profile,
+    api::forge::{Installer, Promotions},
+    api::neoforge::{self, Metadata},
+    api::official::{Assets, Version},
+    api::optifine::OptiFine,
+    api::{hash_reader, http_client, sha1_file, DomainReplacer, Sha1Compare},
     config::{MCLoader, RuntimeConfig},
+    install::progress::ProgressReporter,
 };
-use indicatif::{ProgressBar, ProgressStyle};
 use log::warn;
-use regex::Regex;
-use reqwest::header;
+use reqwest::{header, StatusCode};
+use serde::{Deserialize, Serialize};
 use sha1::{Digest, Sha1};
 use std::{
-    cmp::Ordering,
-    collections::VecDeque,
+    collections::{HashMap, HashSet, VecDeque},
     fs,
+    io::{self, Read, Write},
     path::{Path, PathBuf},
-    sync::{mpsc, Arc, Mutex},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        mpsc, Arc, Mutex,
+    },
     thread,
+    time::{Duration, Instant},
     borrow::Cow,
 };
+use walkdir::WalkDir;
 
-const MAX_THREAD: usize = 32;
+#[cfg(feature = "async")]
+pub mod async_install;
+pub mod pack;
+pub mod progress;
 
-trait Sha1Compare {
-    fn sha1_cmp<C>(&self, sha1code: C) -> Ordering
-    where
-        C: AsRef<str> + Into<String>;
+trait PathExist {
+    fn path_exists(&self) -> bool;
 }
 
-trait DomainReplacer<T> {
-    fn replace_domain(&self, domain: &str) -> T;
+pub trait FileInstall {
+    fn install(
+        &self,
+        reporter: &dyn ProgressReporter,
+        downloader: &Downloader,
+        options: &InstallOptions,
+    ) -> anyhow::Result<InstallReport>;
+
+    /// expected size of the file in bytes, if known
+    fn size(&self) -> Option<u64> {
+        None
+    }
 }
 
-trait PathExist {
-    fn path_exists(&self) -> bool;
+/// what an install run did, the reports of the tasks are summed up by the
+/// pool and `install_mc` sets the duration
+#[derive(Debug, Default, Clone, PartialEq, Serialize)]
+pub struct InstallReport {
+    pub assets_downloaded: usize,
+    pub assets_skipped: usize,
+    /// libraries and natives
+    pub libraries_downloaded: usize,
+    pub libraries_skipped: usize,
+    /// files which failed with `continue_on_error`
+    pub failed: usize,
+    /// bytes of the downloaded files
+    pub bytes_total: u64,
+    pub duration: Duration,
+    /// the downloaded files with the url which served them
+    pub downloads: Vec<DownloadRecord>,
 }
 
-pub trait FileInstall {
-    fn install(&self, bar: &ProgressBar) -> anyhow::Result<()>;
+/// a downloaded file, recorded to tell which mirror served it
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DownloadRecord {
+    pub url: String,
+    pub sha1: String,
+    /// host of the url
+    pub mirror: String,
+    /// true if the primary url failed and the fallback mirror served it
+    pub fallback: bool,
+    pub save_file: PathBuf,
 }
 
-impl DomainReplacer<String> for String {
-    fn replace_domain(&self, domain: &str) -> String {
-        let regex = Regex::new(r"(?<replace>https://\S+?/)").unwrap();
-        let replace = regex.captures(self.as_str()).unwrap();
-        self.replace(&replace["replace"], domain)
+/// `.download-manifest.json` of a version, the files downloaded by its last
+/// install with `download_manifest`
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DownloadManifest {
+    pub version: String,
+    pub downloads: Vec<DownloadRecord>,
+}
+
+/// return the download manifest file of `config.game_version`
+pub fn download_manifest_file(config: &RuntimeConfig) -> PathBuf {
+    config
+        .versions_dir()
+        .join(&config.game_version)
+        .join(".download-manifest.json")
+}
+
+/// write `downloads` sorted by save file into the download manifest file
+fn write_download_manifest(
+    config: &RuntimeConfig,
+    downloads: &[DownloadRecord],
+) -> anyhow::Result<()> {
+    let mut downloads = downloads.to_vec();
+    downloads.sort_by(|a, b| a.save_file.cmp(&b.save_file));
+    let manifest = DownloadManifest {
+        version: config.game_version.clone(),
+        downloads,
+    };
+    let file = download_manifest_file(config);
+    fs::create_dir_all(file.parent().unwrap())?;
+    fs::write(file, serde_json::to_vec_pretty(&manifest)?)?;
+    Ok(())
+}
+
+#[test]
+fn test_write_download_manifest() {
+    let dir = crate::TestDir::new("download_manifest");
+    let config = RuntimeConfig {
+        game_dir: dir.to_path_buf(),
+        game_version: "1.20.4".into(),
+        ..Default::default()
+    };
+    let task = |save_file: &str| InstallTask {
+        sha1: Some("abcd".into()),
+        save_file: save_file.into(),
+        ..Default::default()
+    };
+    let downloads = [
+        task("b").record("https://bmclapi2.bangbang93.com/assets/ab/abcd", false),
+        task("a").record("https://resources.download.minecraft.net/ab/abcd", true),
+    ];
+    write_download_manifest(&config, &downloads).unwrap();
+    let text = fs::read_to_string(download_manifest_file(&config)).unwrap();
+    let manifest: DownloadManifest = serde_json::from_str(&text).unwrap();
+    assert_eq!(manifest.version, "1.20.4");
+    assert_eq!(
+        manifest.downloads[0].mirror,
+        "resources.download.minecraft.net"
+    );
+    assert!(manifest.downloads[0].fallback);
+    assert_eq!(manifest.downloads[1].mirror, "bmclapi2.bangbang93.com");
+    assert_eq!(manifest.downloads[1].sha1, "abcd");
+}
+
+impl std::ops::AddAssign for InstallReport {
+    fn add_assign(&mut self, other: Self) {
+        self.assets_downloaded += other.assets_downloaded;
+        self.assets_skipped += other.assets_skipped;
+        self.libraries_downloaded += other.libraries_downloaded;
+        self.libraries_skipped += other.libraries_skipped;
+        self.failed += other.failed;
+        self.bytes_total += other.bytes_total;
+        self.downloads.extend(other.downloads);
     }
 }
 
-impl<T> Sha1Compare for T
-where
-    T: AsRef<[u8]>,
-{
-    fn sha1_cmp<C>(&self, sha1code: C) -> Ordering
-    where
-        C: AsRef<str> + Into<String>,
-    {
-        let mut hasher = Sha1::new();
-        hasher.update(self);
-        let sha1 = hasher.finalize();
-        hex::encode(sha1).cmp(&sha1code.into())
+/// set by `interrupt`, checked by the download threads between chunks
+static INTERRUPTED: AtomicBool = AtomicBool::new(false);
+
+/// ask the running install to stop, downloads stop at their next chunk and
+/// keep their `.part` file for resuming, it is safe to call from a signal
+/// handler
+pub fn interrupt() {
+    INTERRUPTED.store(true, Ordering::SeqCst);
+}
+
+fn is_interrupted() -> bool {
+    INTERRUPTED.load(Ordering::SeqCst)
+}
+
+/// Error of an install stopped by `interrupt`
+#[derive(Debug)]
+pub struct Interrupted {
+    pub installed: usize,
+    pub total: usize,
+}
+
+impl std::fmt::Display for Interrupted {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "install interrupted, {} of {} files installed, run install again to resume",
+            self.installed, self.total
+        )
     }
 }
 
+impl std::error::Error for Interrupted {}
+
+/// options of one install run, set from the command line
+#[derive(Debug, Default, Clone)]
+pub struct InstallOptions {
+    /// download every file again, even if the cached copy verifies
+    pub force: bool,
+    /// only warn about the files which fail to install
+    pub continue_on_error: bool,
+    /// fetch the version manifest even if the cached one is fresh
+    pub refresh: bool,
+    /// install from the installed files only, without any network call
+    pub offline: bool,
+    /// accept files whose metadata has no sha1 on HTTP 200, files of the
+    /// official servers are always verified
+    pub no_verify: bool,
+    /// start downloading even if the disk looks too full for the files
+    pub skip_space_check: bool,
+    /// write the downloaded files with their mirror into
+    /// `versions/<id>/.download-manifest.json`, unless nothing was
+    /// downloaded
+    pub download_manifest: bool,
+}
+
+/// hosts of the official servers, their files must have a sha1
+const OFFICIAL_HOSTS: [&str; 6] = [
+    "piston-meta.mojang.com",
+    "piston-data.mojang.com",
+    "launchermeta.mojang.com",
+    "launcher.mojang.com",
+    "libraries.minecraft.net",
+    "resources.download.minecraft.net",
+];
+
+/// return true if `url` is on one of the official servers
+fn is_official_url(url: &str) -> bool {
+    reqwest::Url::parse(url).is_ok_and(|x| {
+        x.host_str()
+            .is_some_and(|host| OFFICIAL_HOSTS.contains(&host))
+    })
+}
+
+#[test]
+fn test_is_official_url() {
+    assert!(is_official_url(
+        "https://piston-data.mojang.com/v1/objects/abc/client.jar"
+    ));
+    assert!(!is_official_url(
+        "https://bmclapi2.bangbang93.com/v1/objects/abc/client.jar"
+    ));
+    assert!(!is_official_url("not a url"));
+}
+
 impl<T> PathExist for T
 where
     T: AsRef<Path>,
@@ -75,6 +253,7 @@ pub enum InstallType {
     #[default]
     Asset,
     Library,
+    Native,
     Client,
 }
 
@@ -84,6 +263,10 @@ pub struct InstallTask {
     pub sha1: Option<String>,
     pub save_file: PathBuf,
     pub r#type: InstallType,
+    /// url on the fallback mirror, tried once `url` runs out of retries
+    pub fallback_url: Option<String>,
+    /// size in bytes recorded in the version json or asset index
+    pub size: Option<u64>,
 }
 
 #[derive(Debug, Default, Clone)]
@@ -94,54 +277,590 @@ where
     pub pool: Arc<Mutex<VecDeque<T>>>,
 }
 
-fn fetch_bytes(url: &String, sha1: &Option<String>) -> anyhow::Result<bytes::Bytes> {
-    let client = reqwest::blocking::Client::new();
-    for _ in 0..5 {
-        let send = client
-            .get(url)
-            .header(header::USER_AGENT, "github.com/funny233-github/MCLauncher")
-            .send();
-        let data = send.and_then(|x| x.bytes());
-        if let Ok(_data) = data {
-            if sha1.is_none() {
-                return Ok(_data);
+/// return the temporary file which `save_file` is downloaded into
+fn part_file(save_file: &Path) -> PathBuf {
+    let mut name = save_file.file_name().unwrap_or_default().to_owned();
+    name.push(".part");
+    save_file.with_file_name(name)
+}
+
+/// sha1 and length of the data streamed into the `.part` file of `url`,
+/// shared by the blocking and the async downloads
+struct PartHasher {
+    url: String,
+    part: PathBuf,
+    hasher: Sha1,
+    written: u64,
+    size: Option<u64>,
+}
+
+/// how a download continues `.part` after the status of the response
+enum PartStart {
+    /// append to the part, whose data is hashed already
+    Append(PartHasher),
+    /// write the part from the start
+    Create(PartHasher),
+    /// the part is already complete, with its sha1
+    Complete(String),
+}
+
+impl PartHasher {
+    /// return the size mismatch Error of `len` bytes, the part file is deleted
+    /// as it can not be resumed
+    fn size_mismatch(&self, len: u64) -> anyhow::Error {
+        let _ = fs::remove_file(&self.part);
+        let size = self.size.unwrap_or_default();
+        anyhow::anyhow!(
+            "size mismatch: {} has {len} bytes, expected {size}",
+            self.url
+        )
+    }
+
+    /// check the `Content-Length` of the rest of the file against `size`
+    fn check_length(&self, content_length: Option<u64>) -> anyhow::Result<()> {
+        match content_length {
+            Some(len) if self.size.is_some_and(|x| x != self.written + len) => {
+                Err(self.size_mismatch(self.written + len))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    fn update(&mut self, chunk: &[u8]) {
+        self.hasher.update(chunk);
+        self.written += chunk.len() as u64;
+    }
+
+    /// return the sha1 of the part file once the body is streamed
+    fn finish(self) -> anyhow::Result<String> {
+        if self.size.is_some_and(|x| x != self.written) {
+            return Err(self.size_mismatch(self.written));
+        }
+        Ok(hex::encode(self.hasher.finalize()))
+    }
+}
+
+/// decide how to continue `part` of `url`, which has `offset` bytes and was
+/// requested from there, after the response `status`
+/// # Error
+/// Return Error for an error status, or a size mismatch if the complete part
+/// differs from `size`
+fn start_part(
+    url: &str,
+    part: &Path,
+    offset: u64,
+    size: Option<u64>,
+    status: StatusCode,
+) -> anyhow::Result<PartStart> {
+    let mut hasher = PartHasher {
+        url: url.to_owned(),
+        part: part.to_owned(),
+        hasher: Sha1::new(),
+        written: 0,
+        size,
+    };
+    match status {
+        StatusCode::PARTIAL_CONTENT => {
+            hash_reader(&mut hasher.hasher, fs::File::open(part)?)?;
+            hasher.written = offset;
+            Ok(PartStart::Append(hasher))
+        }
+        // the part file is already complete, let the sha1 check decide
+        StatusCode::RANGE_NOT_SATISFIABLE if offset > 0 => {
+            if size.is_some_and(|x| x != offset) {
+                return Err(hasher.size_mismatch(offset));
             }
-            if _data.sha1_cmp(sha1.as_ref().unwrap()).is_eq() {
-                return Ok(_data);
+            Ok(PartStart::Complete(sha1_file(part)?))
+        }
+        status if status.is_client_error() || status.is_server_error() => {
+            anyhow::bail!("HTTP status {status} for url ({url})")
+        }
+        _ => Ok(PartStart::Create(hasher)),
+    }
+}
+
+#[test]
+fn test_start_part() {
+    let dir = crate::TestDir::new("start_part");
+    let part = dir.join("client.jar.part");
+    fs::write(&part, "ab").unwrap();
+    let start = start_part("u", &part, 2, Some(3), StatusCode::PARTIAL_CONTENT).unwrap();
+    let PartStart::Append(mut hasher) = start else {
+        panic!("the part is not resumed");
+    };
+    assert!(hasher.check_length(Some(2)).is_err());
+    hasher.update(b"c");
+    assert_eq!(hasher.finish().unwrap(), hex::encode(Sha1::digest("abc")));
+    assert!(start_part("u", &part, 0, None, StatusCode::NOT_FOUND).is_err());
+    let start = start_part("u", &part, 0, None, StatusCode::OK).unwrap();
+    assert!(matches!(start, PartStart::Create(_)));
+    let complete = start_part("u", &part, 2, Some(9), StatusCode::RANGE_NOT_SATISFIABLE);
+    assert!(complete.is_err());
+    assert!(!part.exists());
+}
+
+/// rename `part` of `task` to its save file if `hash` verifies, or delete it
+/// and return false, a corrupt part file can not be resumed
+fn finish_part(task: &InstallTask, part: &Path, hash: &str) -> io::Result<bool> {
+    if task.verifies(hash) {
+        fs::rename(part, &task.save_file)?;
+        return Ok(true);
+    }
+    warn!("{} does not match its sha1", part.display());
+    fs::remove_file(part)?;
+    Ok(false)
+}
+
+/// token bucket shared by the download threads to keep the total download
+/// rate around `rate` bytes per second
+#[derive(Debug)]
+struct Bandwidth {
+    rate: f64,
+    /// available bytes, negative when the threads are ahead of the rate
+    bucket: Mutex<(f64, Instant)>,
+}
+
+impl Bandwidth {
+    fn new(rate: u64) -> Self {
+        Bandwidth {
+            rate: rate as f64,
+            bucket: Mutex::new((rate as f64, Instant::now())),
+        }
+    }
+
+    /// take `bytes` from the bucket at `now` and return how long the caller
+    /// has to sleep, the bucket holds at most one second of data
+    fn reserve(&self, bytes: usize, now: Instant) -> Duration {
+        let mut bucket = self.bucket.lock().unwrap();
+        let (tokens, last) = *bucket;
+        let refill = now.saturating_duration_since(last).as_secs_f64() * self.rate;
+        let tokens = (tokens + refill).min(self.rate) - bytes as f64;
+        *bucket = (tokens, now.max(last));
+        Duration::from_secs_f64((-tokens / self.rate).max(0.0))
+    }
+
+    fn take(&self, bytes: usize) {
+        thread::sleep(self.reserve(bytes, Instant::now()));
+    }
+}
+
+#[test]
+fn test_bandwidth() {
+    let bandwidth = Bandwidth::new(1000);
+    let now = Instant::now();
+    assert_eq!(bandwidth.reserve(1000, now), Duration::ZERO);
+    assert_eq!(bandwidth.reserve(500, now), Duration::from_millis(500));
+    // the debt of a thread delays the next one
+    assert_eq!(bandwidth.reserve(500, now), Duration::from_secs(1));
+    let later = now + Duration::from_secs(5);
+    assert_eq!(bandwidth.reserve(0, later), Duration::ZERO);
+}
+
+/// downloads of an install run, sharing the http client of the launcher
+#[derive(Debug, Clone)]
+pub struct Downloader {
+    client: reqwest::blocking::Client,
+    timeout: Duration,
+    retries: usize,
+    bandwidth: Option<Arc<Bandwidth>>,
+}
+
+impl Downloader {
+    /// set up downloads with `download_timeout_secs`, `download_retries` and
+    /// `max_bandwidth` of `config`
+    pub fn new(config: &RuntimeConfig) -> anyhow::Result<Self> {
+        let bandwidth = (config.max_bandwidth > 0)
+            .then(|| Arc::new(Bandwidth::new(config.max_bandwidth * 1024)));
+        Ok(Downloader {
+            client: http_client(),
+            timeout: Duration::from_secs(config.download_timeout_secs),
+            retries: config.download_retries,
+            bandwidth,
+        })
+    }
+
+    /// download `url` to the save file of `task` through a `.part` file, which
+    /// is renamed to the save file once the size and sha1 of the streamed data
+    /// match, and kept for resuming otherwise
+    fn download(&self, url: &str, task: &InstallTask) -> anyhow::Result<()> {
+        let save_file = &task.save_file;
+        let part = part_file(save_file);
+        fs::create_dir_all(save_file.parent().unwrap())?;
+        for attempt in 0..self.retries {
+            if is_interrupted() {
+                anyhow::bail!("interrupted");
+            }
+            if attempt > 0 {
+                warn!("retry {url}, attempt {}/{}", attempt + 1, self.retries);
+                thread::sleep(backoff(attempt - 1));
+            }
+            match self.download_part(url, &part, task.size) {
+                Ok(hash) => {
+                    if finish_part(task, &part, &hash)? {
+                        return Ok(());
+                    }
+                }
+                Err(e) => warn!("download {url} fail: {e}"),
+            }
+        }
+        Err(anyhow::anyhow!("download {url} fail"))
+    }
+
+    /// fetch the small file `url` into memory with the same retries, backoff
+    /// and timeout as the downloads, the body has to match `sha1` if given
+    fn fetch(&self, url: &str, sha1: Option<&str>) -> anyhow::Result<bytes::Bytes> {
+        for attempt in 0..self.retries {
+            if attempt > 0 {
+                warn!("retry {url}, attempt {}/{}", attempt + 1, self.retries);
+                thread::sleep(backoff(attempt - 1));
             }
+            let res = self
+                .client
+                .get(url)
+                .timeout(self.timeout)
+                .send()
+                .and_then(|x| x.error_for_status())
+                .and_then(|x| x.bytes());
+            match res {
+                Ok(data) if sha1.is_none_or(|x| data.sha1_matches(x)) => return Ok(data),
+                Ok(_) => warn!("{url} does not match its sha1"),
+                Err(e) => warn!("fetch {url} fail: {e}"),
+            }
+        }
+        Err(anyhow::anyhow!("fetch {url} fail"))
+    }
+
+    /// download `url` into `part` and return the sha1 of `part`, resuming from
+    /// the current length of `part` when the server supports range requests
+    /// the body is written in chunks throttled by `bandwidth` and hashed on the
+    /// way, so memory stays flat for any file size
+    /// # Error
+    /// Return a size mismatch Error before hashing if `Content-Length` or the
+    /// written bytes differ from `size`, the part file is deleted then
+    fn download_part(&self, url: &str, part: &Path, size: Option<u64>) -> anyhow::Result<String> {
+        let offset = fs::metadata(part).map(|x| x.len()).unwrap_or(0);
+        let mut request = self.client.get(url).timeout(self.timeout);
+        if offset > 0 {
+            request = request.header(header::RANGE, format!("bytes={offset}-"));
+        }
+        let mut response = request.send()?;
+        let (mut file, mut hasher) = match start_part(url, part, offset, size, response.status())? {
+            PartStart::Complete(hash) => return Ok(hash),
+            PartStart::Append(hasher) => (fs::OpenOptions::new().append(true).open(part)?, hasher),
+            PartStart::Create(hasher) => (fs::File::create(part)?, hasher),
         };
-        warn!("install fail, then retry");
-        thread::sleep(std::time::Duration::from_secs(3));
+        hasher.check_length(response.content_length())?;
+        let mut buf = [0; 16 * 1024];
+        loop {
+            let len = response.read(&mut buf)?;
+            if len == 0 {
+                return hasher.finish();
+            }
+            if is_interrupted() {
+                anyhow::bail!("interrupted");
+            }
+            if let Some(bandwidth) = &self.bandwidth {
+                bandwidth.take(len);
+            }
+            hasher.update(&buf[..len]);
+            file.write_all(&buf[..len])?;
+        }
     }
-    Err(anyhow::anyhow!("download {url} fail"))
 }
 
-impl FileInstall for InstallTask {
-    fn install(&self, bar: &ProgressBar) -> anyhow::Result<()> {
-        if self.sha1.is_none()
-            || !(self.save_file.path_exists()
-                && fs::read(&self.save_file)
-                    .unwrap()
-                    .sha1_cmp(self.sha1.as_ref().unwrap())
-                    .is_eq())
-        {
-            let data = fetch_bytes(&self.url, &self.sha1)?;
-            fs::create_dir_all(self.save_file.parent().unwrap()).unwrap();
-            fs::write(&self.save_file, data).unwrap();
-        }
-        bar.inc(1);
+/// return the sum of the sizes of `tasks`, or None if a task has no size as
+/// in very old asset indexes and loader libraries
+fn total_bytes<'a, T: FileInstall + 'a>(tasks: impl IntoIterator<Item = &'a T>) -> Option<u64> {
+    tasks.into_iter().map(|x| x.size()).sum()
+}
+
+#[test]
+fn test_total_bytes() {
+    let task = |size| InstallTask {
+        size,
+        ..Default::default()
+    };
+    assert_eq!(total_bytes(&[task(Some(3)), task(Some(4))]), Some(7));
+    assert_eq!(total_bytes(&[task(Some(3)), task(None)]), None);
+}
+
+/// return the delay before retry `attempt + 1`: 1s, 2s, 4s ... up to 32s,
+/// with a random jitter of a quarter of the delay in both directions
+fn backoff(attempt: usize) -> Duration {
+    use std::hash::{BuildHasher, Hasher};
+    let delay = 1000u64 << attempt.min(5);
+    let random = std::collections::hash_map::RandomState::new()
+        .build_hasher()
+        .finish();
+    let jitter = random % (delay / 2 + 1);
+    Duration::from_millis(delay - delay / 4 + jitter)
+}
+
+#[test]
+fn test_backoff() {
+    for (attempt, delay) in [(0, 1000), (1, 2000), (2, 4000), (9, 32000)] {
+        let millis = backoff(attempt).as_millis() as u64;
+        assert!((delay - delay / 4..=delay + delay / 4).contains(&millis));
+    }
+}
+
+/// map `items` with `f` on one thread per cpu core, for hashing files which
+/// is bound by the cpu on fast disks, the results keep the order of `items`
+fn par_map<T: Sync, R: Send>(items: &[T], f: impl Fn(&T) -> R + Sync) -> Vec<R> {
+    let threads = thread::available_parallelism().map_or(1, |x| x.get());
+    let chunk = items.len().div_ceil(threads).max(1);
+    thread::scope(|scope| {
+        let handles: Vec<_> = items
+            .chunks(chunk)
+            .map(|chunk| scope.spawn(|| chunk.iter().map(&f).collect::<Vec<_>>()))
+            .collect();
+        handles
+            .into_iter()
+            .flat_map(|x| x.join().unwrap())
+            .collect()
+    })
+}
+
+#[test]
+fn test_par_map() {
+    let items: Vec<usize> = (0..1000).collect();
+    let doubled = par_map(&items, |x| x * 2);
+    assert_eq!(doubled, items.iter().map(|x| x * 2).collect::<Vec<_>>());
+    assert!(par_map(&[] as &[usize], |x| *x).is_empty());
+}
+
+/// state of the save file of a task, checked by `InstallTask::check`
+#[derive(Debug, PartialEq)]
+enum FileState {
+    Missing,
+    Mismatched,
+    Ok,
+}
+
+impl InstallTask {
+    /// return whether the save file is missing or does not match the size
+    /// and sha1, a task without sha1 is ok once its file exists
+    fn check(&self) -> FileState {
+        if !self.save_file.path_exists() {
+            FileState::Missing
+        } else if self.sha1.is_some() && !self.is_cached() {
+            FileState::Mismatched
+        } else {
+            FileState::Ok
+        }
+    }
+
+    /// return true if the save file exists and matches the size and sha1, the
+    /// size is checked first so truncated files are not hashed
+    fn is_cached(&self) -> bool {
+        let Some(sha1) = &self.sha1 else {
+            return false;
+        };
+        let Ok(metadata) = fs::metadata(&self.save_file) else {
+            return false;
+        };
+        if self.size.is_some_and(|x| x != metadata.len()) {
+            return false;
+        }
+        sha1_file(&self.save_file).is_ok_and(|x| x.eq_ignore_ascii_case(sha1))
+    }
+}
+
+#[test]
+fn test_corrupt_file_is_not_cached() {
+    let dir = crate::TestDir::new("corrupt_file");
+    let data = b"asset data";
+    let task = InstallTask {
+        sha1: Some(hex::encode(Sha1::digest(data))),
+        save_file: dir.join("asset"),
+        ..Default::default()
+    };
+    fs::write(&task.save_file, b"asset dat").unwrap();
+    assert!(!task.is_cached());
+    fs::write(&task.save_file, data).unwrap();
+    assert!(task.is_cached());
+    let wrong_size = InstallTask {
+        size: Some(data.len() as u64 + 1),
+        ..task.clone()
+    };
+    assert!(!wrong_size.is_cached());
+    let task = InstallTask {
+        sha1: task.sha1.map(|x| x.to_uppercase()),
+        ..task
+    };
+    assert!(task.is_cached());
+}
+
+impl InstallTask {
+    /// return the name of the task in progress and error messages
+    fn name(&self) -> String {
         match &self.r#type {
-            InstallType::Asset => {
-                bar.set_message(format!("Asset {} installed", self.sha1.as_ref().unwrap()))
+            InstallType::Asset => format!("asset {}", self.sha1.as_deref().unwrap_or_default()),
+            InstallType::Library => format!("library {:?}", self.save_file.file_name().unwrap()),
+            InstallType::Native => format!("native {:?}", self.save_file.file_name().unwrap()),
+            InstallType::Client => "client".to_owned(),
+        }
+    }
+
+    /// check the task can be verified, a client without sha1 needs
+    /// `no_verify` and a mirror which is not official
+    fn check_verifiable(&self, options: &InstallOptions) -> anyhow::Result<()> {
+        if self.sha1.is_none() && self.r#type == InstallType::Client {
+            if !options.no_verify || is_official_url(&self.url) {
+                anyhow::bail!(
+                    "{}: the version json has no sha1 of it, pass --no-verify to install \
+                     it unverified from a mirror without checksums",
+                    self.name()
+                );
             }
-            InstallType::Library => bar.set_message(format!(
-                "library {:?} installed",
-                self.save_file.file_name().unwrap()
-            )),
-            InstallType::Client => bar.set_message("client installed"),
+            warn!(
+                "{} has no sha1, install it unverified from {}",
+                self.name(),
+                self.url
+            );
         }
         Ok(())
     }
+
+    /// return true if `hash` of the downloaded data is the sha1 of the task,
+    /// a task without sha1 accepts any data
+    fn verifies(&self, hash: &str) -> bool {
+        self.sha1
+            .as_ref()
+            .is_none_or(|x| hash.eq_ignore_ascii_case(x))
+    }
+
+    /// return the report of the installed task, `bytes` is the size of the
+    /// save file
+    fn report(&self, bytes: u64, cached: bool) -> InstallReport {
+        let mut report = InstallReport::default();
+        match (&self.r#type, cached) {
+            (InstallType::Asset, false) => report.assets_downloaded = 1,
+            (InstallType::Asset, true) => report.assets_skipped = 1,
+            (InstallType::Library | InstallType::Native, false) => report.libraries_downloaded = 1,
+            (InstallType::Library | InstallType::Native, true) => report.libraries_skipped = 1,
+            (InstallType::Client, _) => {}
+        }
+        if !cached {
+            report.bytes_total = bytes;
+        }
+        report
+    }
+
+    /// return the record of the task downloaded from `url`, the sha1 of a
+    /// task without one is read from the save file
+    fn record(&self, url: &str, fallback: bool) -> DownloadRecord {
+        let sha1 = self
+            .sha1
+            .clone()
+            .or_else(|| sha1_file(&self.save_file).ok())
+            .unwrap_or_default();
+        let mirror = reqwest::Url::parse(url)
+            .ok()
+            .and_then(|x| x.host_str().map(str::to_owned))
+            .unwrap_or_default();
+        DownloadRecord {
+            url: url.to_owned(),
+            sha1,
+            mirror,
+            fallback,
+            save_file: self.save_file.clone(),
+        }
+    }
+}
+
+impl FileInstall for InstallTask {
+    fn size(&self) -> Option<u64> {
+        self.size
+    }
+
+    fn install(
+        &self,
+        reporter: &dyn ProgressReporter,
+        downloader: &Downloader,
+        options: &InstallOptions,
+    ) -> anyhow::Result<InstallReport> {
+        let name = self.name();
+        if let Err(e) = self.check_verifiable(options) {
+            reporter.on_file_failed(&name, &e.to_string());
+            return Err(e);
+        }
+        let cached = !options.force && self.is_cached();
+        let mut served = None;
+        if !cached {
+            let res = downloader
+                .download(&self.url, self)
+                .map(|_| (&self.url, false));
+            let res = match (res, &self.fallback_url) {
+                (Err(e), Some(url)) => {
+                    warn!("{e}, fall back to {url}");
+                    let res = downloader.download(url, self).map(|_| (url, true));
+                    if res.is_ok() {
+                        warn!("{url} served by the fallback mirror");
+                    }
+                    res
+                }
+                (res, _) => res,
+            };
+            match res {
+                Ok(x) => served = Some(x),
+                Err(e) => {
+                    let e = anyhow::anyhow!("{name}: {e}");
+                    reporter.on_file_failed(&name, &e.to_string());
+                    return Err(e);
+                }
+            }
+        }
+        let bytes = fs::metadata(&self.save_file).map_or(0, |x| x.len());
+        reporter.on_file_done(&name, bytes, cached);
+        let mut report = self.report(bytes, cached);
+        if let Some((url, fallback)) = served {
+            report.downloads.push(self.record(url, fallback));
+        }
+        Ok(report)
+    }
+}
+
+#[test]
+fn test_install_report_of_cached_file() {
+    let dir = crate::TestDir::new("install_report");
+    let task = InstallTask {
+        sha1: Some(hex::encode(Sha1::digest("asset"))),
+        save_file: dir.join("asset"),
+        ..Default::default()
+    };
+    fs::write(&task.save_file, "asset").unwrap();
+    let downloader = Downloader::new(&RuntimeConfig::default()).unwrap();
+    let options = InstallOptions::default();
+    let report = task
+        .install(&progress::SilentReporter, &downloader, &options)
+        .unwrap();
+    assert_eq!(report.assets_skipped, 1);
+    assert_eq!(report.bytes_total, 0);
+}
+
+#[test]
+fn test_client_without_sha1() {
+    let dir = crate::TestDir::new("client_without_sha1");
+    let task = InstallTask {
+        url: "https://piston-data.mojang.com/v1/objects/abc/client.jar".into(),
+        save_file: dir.join("client.jar"),
+        r#type: InstallType::Client,
+        ..Default::default()
+    };
+    let config = RuntimeConfig::default();
+    let downloader = Downloader::new(&config).unwrap();
+    let reporter = progress::SilentReporter;
+    let err = task
+        .install(&reporter, &downloader, &InstallOptions::default())
+        .unwrap_err();
+    assert!(err.to_string().contains("--no-verify"));
+    // official files are verified even with no_verify
+    let options = InstallOptions {
+        no_verify: true,
+        ..Default::default()
+    };
+    assert!(task.install(&reporter, &downloader, &options).is_err());
 }
 
 impl<T> TaskPool<T>
@@ -254,156 +973,1355 @@ where
         self.pool.lock().unwrap().append(other);
     }
 
-    //Execute all install task.
+    //Execute all install task with `threads` workers sharing `downloader`,
+    //no more workers than tasks are started, the progress goes to `reporter`.
     //# Error
-    //Return Error when install fail 5 times
-    pub fn install(self) -> anyhow::Result<()> {
+    //Every task is tried even if some fail, then return one Error listing
+    //the failed tasks, or only warn about them with `continue_on_error` and
+    //count them in the returned report
+    pub fn install(
+        self,
+        threads: usize,
+        downloader: &Downloader,
+        options: &InstallOptions,
+        reporter: &dyn ProgressReporter,
+    ) -> anyhow::Result<InstallReport> {
         let (tx, rx) = mpsc::channel();
-        let bar = ProgressBar::new(self.len() as u64);
-        bar.set_style(
-            ProgressStyle::with_template(
-                "[{elapsed_precise}] {bar:40.cyan/blue} {pos:>7}/{len:7} {msg}",
-            )
-            .unwrap()
-            .progress_chars("##-"),
-        );
-        let mut handles = vec![];
-        for _ in 0..MAX_THREAD {
-            let tasks_share = self.clone();
-            let bar_share = bar.clone();
-            let tx_share = tx.clone();
-            let thr = thread::spawn(move || loop {
-                if let Some(task) = tasks_share.pop_back() {
-                    tx_share.send(task.install(&bar_share)).unwrap();
-                } else {
-                    return;
-                }
-            });
-            handles.push(thr);
+        let total = self.len();
+        reporter.on_start(total as u64);
+        if let Some(bytes) = total_bytes(self.pool.lock().unwrap().iter()) {
+            reporter.on_total_bytes(bytes);
         }
-        drop(tx);
-        for received in rx {
-            received?;
+        let mut report = InstallReport::default();
+        let mut installed = 0;
+        let mut failed = vec![];
+        thread::scope(|scope| {
+            for _ in 0..threads.clamp(1, total.max(1)) {
+                let tx = tx.clone();
+                let tasks = &self;
+                scope.spawn(move || {
+                    // the started task finishes or stops at its next chunk
+                    while !is_interrupted() {
+                        let Some(task) = tasks.pop_back() else {
+                            break;
+                        };
+                        tx.send(task.install(reporter, downloader, options))
+                            .unwrap();
+                    }
+                });
+            }
+            drop(tx);
+            for received in rx {
+                match received {
+                    Ok(x) => {
+                        report += x;
+                        installed += 1;
+                    }
+                    Err(e) => failed.push(e.to_string()),
+                }
+            }
+        });
+        reporter.on_finish();
+        if is_interrupted() {
+            return Err(Interrupted { installed, total }.into());
         }
-        for handle in handles {
-            handle.join().unwrap();
+        check_failed(failed, total, options, &mut report)?;
+        Ok(report)
+    }
+}
+
+/// return one Error listing the `failed` of `total` tasks, or only warn about
+/// them with `continue_on_error` and count them in `report`
+fn check_failed(
+    failed: Vec<String>,
+    total: usize,
+    options: &InstallOptions,
+    report: &mut InstallReport,
+) -> anyhow::Result<()> {
+    if failed.is_empty() {
+        return Ok(());
+    }
+    let summary = format!(
+        "{} of {total} files failed to install:\n  - {}",
+        failed.len(),
+        failed.join("\n  - ")
+    );
+    if options.continue_on_error {
+        warn!("{summary}");
+        report.failed = failed.len();
+        return Ok(());
+    }
+    anyhow::bail!("{summary}, run install again to retry them")
+}
+
+#[cfg(test)]
+#[derive(Debug, Clone)]
+struct FailingTask(usize);
+
+#[cfg(test)]
+impl FileInstall for FailingTask {
+    fn install(
+        &self,
+        _: &dyn ProgressReporter,
+        _: &Downloader,
+        _: &InstallOptions,
+    ) -> anyhow::Result<InstallReport> {
+        if self.0.is_multiple_of(2) {
+            anyhow::bail!("task {} failed", self.0);
         }
-        Ok(())
+        Ok(InstallReport {
+            assets_downloaded: 1,
+            ..Default::default()
+        })
     }
 }
 
-pub fn install_mc(config: &RuntimeConfig) -> anyhow::Result<()> {
-    println!("fetch version manifest...");
-    let manifest = VersionManifest::fetch(&config.mirror.version_manifest)?;
-    println!("fetch version...");
+#[test]
+fn test_failed_tasks_are_collected() {
+    use progress::SilentReporter;
+    let downloader = Downloader::new(&RuntimeConfig::default()).unwrap();
+    let pool = || {
+        let pool = TaskPool::new();
+        (0..5).for_each(|x| pool.push_back(FailingTask(x)));
+        pool
+    };
+    let err = pool()
+        .install(2, &downloader, &InstallOptions::default(), &SilentReporter)
+        .unwrap_err()
+        .to_string();
+    assert!(err.starts_with("3 of 5 files failed"));
+    assert!(err.contains("task 0 failed") && err.contains("task 4 failed"));
+    let options = InstallOptions {
+        continue_on_error: true,
+        ..Default::default()
+    };
+    let report = pool()
+        .install(2, &downloader, &options, &SilentReporter)
+        .unwrap();
+    assert_eq!(report.assets_downloaded, 2);
+    assert_eq!(report.failed, 3);
+}
+
+/// fetch the version json of `config.game_version` merged with the loader
+/// profile, the libraries bundled in the forge installer or the optifine jar
+/// are copied into `libraries_dir`, or skipped without it
+fn fetch_version(
+    config: &RuntimeConfig,
+    libraries_dir: Option<&Path>,
+    options: &InstallOptions,
+    reporter: &dyn ProgressReporter,
+) -> anyhow::Result<Version> {
+    reporter.on_stage("fetch version manifest");
+    let manifest = config.version_manifest(options.refresh)?;
+    reporter.on_stage("fetch version");
     let mut version = Version::fetch(
         manifest,
         &config.game_version,
         &config.mirror.version_manifest,
     )?;
     if let MCLoader::Fabric(v) = &config.loader {
-        println!("fetch fabric profile...");
+        reporter.on_stage("fetch fabric profile");
         let game_version = Cow::from(&config.game_version);
         let loader_version = Cow::from(v);
-        let profile = Profile::fetch(&config.mirror.fabric_meta,game_version,loader_version)?;
+        let profile = Profile::fetch(&config.mirror.fabric_meta, game_version, loader_version)?;
+        version.merge(profile)
+    }
+    if let MCLoader::Forge(v) = &config.loader {
+        let forge_version = match v.as_str() {
+            "recommended" => Promotions::fetch()?.recommended(&config.game_version),
+            "latest" => Promotions::fetch()?.latest(&config.game_version),
+            _ => Some(v.clone()),
+        }
+        .ok_or_else(|| anyhow::anyhow!("no forge build for {}", config.game_version))?;
+        reporter.on_stage(&format!("fetch forge {forge_version} installer"));
+        let mut installer = Installer::fetch(
+            &config.mirror.forge_maven,
+            &config.game_version,
+            &forge_version,
+        )?;
+        let profile = match libraries_dir {
+            Some(dir) => installer.install(&dir)?,
+            None => installer.profile()?,
+        };
+        version.merge(profile)
+    }
+    if let MCLoader::NeoForge(v) = &config.loader {
+        let build = match v.as_str() {
+            "latest" => Metadata::fetch(&config.mirror.neoforge_maven, &config.game_version)?
+                .latest(&config.game_version)
+                .ok_or_else(|| anyhow::anyhow!("no neoforge build for {}", config.game_version))?,
+            _ => v.clone(),
+        };
+        reporter.on_stage(&format!("fetch neoforge {build} installer"));
+        let mut installer =
+            neoforge::installer(&config.mirror.neoforge_maven, &config.game_version, &build)?;
+        let profile = match libraries_dir {
+            Some(dir) => installer.install(&dir)?,
+            None => installer.profile()?,
+        };
         version.merge(profile)
     }
+    if let MCLoader::OptiFine(source) = &config.loader {
+        reporter.on_stage(&format!("read optifine {source}"));
+        let profile = OptiFine::fetch(source)?.install(&version, libraries_dir)?;
+        version.merge(profile)
+    }
+    Ok(version)
+}
+
+/// state of an install once the version json and asset index are installed
+enum Prepared {
+    /// every file verified, the version is installed without downloads
+    Installed(InstallReport),
+    Download {
+        version: Box<Version>,
+        assets: Assets,
+        /// files which are missing or do not verify
+        tasks: VecDeque<InstallTask>,
+        /// counts of the files which verified and are kept
+        skipped: InstallReport,
+    },
+}
+
+/// install the version json and asset index of `config.game_version` and
+/// return the files to download, the ones which verify are dropped and
+/// every file is hashed at most once
+/// a version without loader whose files all verify against the local
+/// version json and asset index is installed without network calls,
+/// `offline` fails instead of going to the network
+fn prepare_install(
+    config: &RuntimeConfig,
+    options: &InstallOptions,
+    reporter: &dyn ProgressReporter,
+) -> anyhow::Result<Prepared> {
+    if options.offline {
+        return install_offline(config, reporter).map(Prepared::Installed);
+    }
+    let mut verified = HashSet::new();
+    if !options.force && matches!(config.loader, MCLoader::None) {
+        match installed_version(config) {
+            Ok((version, assets)) => {
+                let mut tasks = mc_installtask(config, &version, &assets)?;
+                reporter.on_stage("check installed files");
+                let report = drop_cached(&mut tasks, &mut verified);
+                if tasks.is_empty() {
+                    reporter.on_stage("all files are installed, skip downloading");
+                    finish_install(config, &version, &assets, reporter)?;
+                    return Ok(Prepared::Installed(report));
+                }
+                log::debug!(
+                    "{} files are missing or corrupt, install from {}",
+                    tasks.len(),
+                    config.mirror.version_manifest
+                );
+            }
+            Err(e) => log::debug!("{e}, install from {}", config.mirror.version_manifest),
+        }
+    }
+    // the merged profile would replace the vanilla json of the other launcher
+    if !matches!(config.loader, MCLoader::None)
+        && config
+            .versions_dir
+            .as_deref()
+            .is_some_and(Path::is_absolute)
+    {
+        anyhow::bail!(
+            "versions_dir {} may be shared with other launchers, install loaders with a \
+             versions_dir in game_dir",
+            config.versions_dir().display()
+        );
+    }
+    fs::create_dir_all(&config.game_dir).map_err(|e| {
+        anyhow::anyhow!("can not create game_dir {}: {e}", config.game_dir.display())
+    })?;
+    let libraries_dir = config.libraries_dir();
+    let version = fetch_version(config, Some(&libraries_dir), options, reporter)?;
 
-    let version_json_file = Path::new(&config.game_dir)
-        .join("versions")
+    let version_json_file = config
+        .versions_dir()
         .join(&config.game_version)
         .join(config.game_version.clone() + ".json");
     version.install(&version_json_file);
-    let native_dir = Path::new(&config.game_dir).join("natives");
-    fs::create_dir_all(native_dir).unwrap_or(());
 
-    let game_dir = &config.game_dir;
-    let game_version = &config.game_version;
-    let asset_index_file = Path::new(game_dir)
-        .join("assets")
-        .join("indexes")
-        .join(version.asset_index.id.clone() + ".json");
-    println!("fetch assets...");
-    let assets = Assets::fetch(&version.asset_index, &config.mirror.version_manifest)?;
-    assets.install(&asset_index_file);
-
-    let tasks = TaskPool::new();
-    tasks.append(&mut assets_installtask(
-        game_dir,
+    let assets = fetch_and_store_asset_index(config, &version, options, reporter)?;
+
+    let mut tasks = mc_installtask(config, &version, &assets)?;
+    if !options.force {
+        let linked = link_shared_assets(config, &tasks);
+        if linked > 0 {
+            reporter.on_stage(&format!("{linked} assets are taken from other profiles"));
+        }
+    }
+    let skipped = if options.force {
+        InstallReport::default()
+    } else {
+        reporter.on_stage("check installed files");
+        drop_cached(&mut tasks, &mut verified)
+    };
+    set_fallback_urls(config, &version, &assets, &mut tasks)?;
+    if !options.skip_space_check {
+        check_space(&config.game_dir, &tasks)?;
+    }
+    Ok(Prepared::Download {
+        version: Box::new(version),
+        assets,
+        tasks,
+        skipped,
+    })
+}
+
+/// hash the save files of `tasks` on one thread per cpu core and drop the
+/// tasks whose file verifies, return their counts as skipped, files without
+/// sha1 can not be verified and are downloaded again
+/// the save file and sha1 of every file which verifies are added to
+/// `verified`, and tasks already in it are dropped without hashing again
+fn drop_cached(
+    tasks: &mut VecDeque<InstallTask>,
+    verified: &mut HashSet<(PathBuf, String)>,
+) -> InstallReport {
+    let mut skipped = InstallReport::default();
+    let mut unknown = vec![];
+    for task in std::mem::take(tasks) {
+        match &task.sha1 {
+            Some(sha1) if verified.contains(&(task.save_file.clone(), sha1.clone())) => {
+                skipped += task.report(0, true);
+            }
+            _ => unknown.push(task),
+        }
+    }
+    let states = par_map(&unknown, InstallTask::check);
+    for (task, state) in unknown.into_iter().zip(states) {
+        match &task.sha1 {
+            Some(sha1) if state == FileState::Ok => {
+                verified.insert((task.save_file.clone(), sha1.clone()));
+                skipped += task.report(0, true);
+            }
+            _ => tasks.push_back(task),
+        }
+    }
+    skipped
+}
+
+#[test]
+fn test_drop_cached() {
+    let dir = crate::TestDir::new("drop_cached");
+    let task = |name: &str, sha1: Option<&str>| InstallTask {
+        sha1: sha1.map(str::to_owned),
+        save_file: dir.join(name),
+        r#type: InstallType::Asset,
+        ..Default::default()
+    };
+    let good = hex::encode(Sha1::digest("good"));
+    fs::write(dir.join("good"), "good").unwrap();
+    fs::write(dir.join("bad"), "tampered").unwrap();
+    fs::write(dir.join("unknown"), "any").unwrap();
+    let mut tasks = VecDeque::from([
+        task("good", Some(&good)),
+        task("bad", Some(&good)),
+        task("missing", Some(&good)),
+        task("unknown", None),
+    ]);
+    let mut verified = HashSet::new();
+    let skipped = drop_cached(&mut tasks, &mut verified);
+    assert_eq!(skipped.assets_skipped, 1);
+    let left: Vec<_> = tasks.iter().map(|x| x.save_file.clone()).collect();
+    assert_eq!(left, ["bad", "missing", "unknown"].map(|x| dir.join(x)));
+    assert_eq!(verified, HashSet::from([(dir.join("good"), good.clone())]));
+
+    // a verified file is not hashed again, even if it changed since
+    fs::write(dir.join("good"), "changed").unwrap();
+    let mut tasks = VecDeque::from([task("good", Some(&good))]);
+    let skipped = drop_cached(&mut tasks, &mut verified);
+    assert_eq!(skipped.assets_skipped, 1);
+    assert!(tasks.is_empty());
+}
+
+/// return the free bytes of the filesystem of `dir` for unprivileged users
+#[cfg(unix)]
+fn available_space(dir: &Path) -> Option<u64> {
+    use std::os::unix::ffi::OsStrExt;
+    let path = std::ffi::CString::new(dir.as_os_str().as_bytes()).ok()?;
+    // SAFETY: statvfs is all integers, so zeroed is a valid value
+    let mut stat: libc::statvfs = unsafe { std::mem::zeroed() };
+    // SAFETY: path is nul terminated and stat is a valid out pointer
+    if unsafe { libc::statvfs(path.as_ptr(), &mut stat) } != 0 {
+        return None;
+    }
+    // the field types differ between platforms
+    #[allow(clippy::unnecessary_cast)]
+    Some((stat.f_bavail as u64).saturating_mul(stat.f_frsize as u64))
+}
+
+#[cfg(not(unix))]
+fn available_space(_dir: &Path) -> Option<u64> {
+    None
+}
+
+/// return an Error if the filesystem of `dir` has less free space than the
+/// missing files of `tasks` plus a margin of a tenth and 100 MB, files
+/// already in place at the expected size are not counted, and the check is
+/// skipped where the free space is unknown
+fn check_space(dir: &Path, tasks: &VecDeque<InstallTask>) -> anyhow::Result<()> {
+    let needed: u64 = tasks
+        .iter()
+        .filter(|x| fs::metadata(&x.save_file).map(|x| x.len()).ok() != x.size)
+        .filter_map(|x| x.size)
+        .sum();
+    let Some(available) = available_space(dir) else {
+        log::debug!("free space of {} is unknown, skip the check", dir.display());
+        return Ok(());
+    };
+    let mb = 1024 * 1024;
+    let required = needed + needed / 10 + 100 * mb;
+    if needed > 0 && available < required {
+        anyhow::bail!(
+            "not enough disk space in {}: {} MB needed, {} MB free, free up space or pass \
+             --skip-space-check",
+            dir.display(),
+            required / mb,
+            available / mb
+        );
+    }
+    Ok(())
+}
+
+#[test]
+fn test_check_space() {
+    let dir = crate::TestDir::new("check_space");
+    let task = |size| InstallTask {
+        save_file: dir.join("client.jar"),
+        size: Some(size),
+        ..Default::default()
+    };
+    assert!(check_space(&dir, &VecDeque::from([task(1024)])).is_ok());
+    let huge = VecDeque::from([task(u64::MAX / 4)]);
+    if available_space(&dir).is_some() {
+        let e = check_space(&dir, &huge).unwrap_err().to_string();
+        assert!(e.contains("--skip-space-check"));
+    }
+}
+
+/// install `config.game_version` with its loader into `config.game_dir`, the
+/// progress goes to `reporter`
+/// a version without loader whose files all verify is installed without
+/// network calls, loader profiles are always fetched unless `offline`
+pub fn install_mc(
+    config: &RuntimeConfig,
+    options: &InstallOptions,
+    reporter: &dyn ProgressReporter,
+) -> anyhow::Result<InstallReport> {
+    let start = Instant::now();
+    let mut report = match prepare_install(config, options, reporter)? {
+        Prepared::Installed(report) => report,
+        Prepared::Download {
+            version,
+            assets,
+            mut tasks,
+            skipped,
+        } => {
+            let pool = TaskPool::new();
+            pool.append(&mut tasks);
+            let downloader = Downloader::new(config)?;
+            let mut report = pool.install(
+                config.max_concurrent_downloads,
+                &downloader,
+                options,
+                reporter,
+            )?;
+            report += skipped;
+            finish_install(config, &version, &assets, reporter)?;
+            if options.download_manifest && !report.downloads.is_empty() {
+                write_download_manifest(config, &report.downloads)?;
+            }
+            report
+        }
+    };
+    report.duration = start.elapsed();
+    Ok(report)
+}
+
+/// fetch the asset index of `version` as served, one without sha1 needs
+/// `no_verify`
+fn fetch_asset_index_data(
+    config: &RuntimeConfig,
+    version: &Version,
+    options: &InstallOptions,
+    reporter: &dyn ProgressReporter,
+) -> anyhow::Result<bytes::Bytes> {
+    reporter.on_stage("fetch assets");
+    let asset_index = &version.asset_index;
+    let url = asset_index
+        .url
+        .replace_domain(&config.mirror.version_manifest);
+    if asset_index.sha1.is_empty() {
+        if !options.no_verify || is_official_url(&url) {
+            anyhow::bail!(
+                "asset index {} has no sha1, pass --no-verify to fetch it unverified",
+                asset_index.id
+            );
+        }
+        warn!(
+            "asset index {} has no sha1, fetch it unverified",
+            asset_index.id
+        );
+    }
+    let sha1 = Some(asset_index.sha1.as_str()).filter(|x| !x.is_empty());
+    Downloader::new(config)?.fetch(&url, sha1)
+}
+
+/// fetch and parse the asset index of `version`
+fn fetch_assets(
+    config: &RuntimeConfig,
+    version: &Version,
+    options: &InstallOptions,
+    reporter: &dyn ProgressReporter,
+) -> anyhow::Result<Assets> {
+    let data = fetch_asset_index_data(config, version, options, reporter)?;
+    Ok(serde_json::from_slice(&data)?)
+}
+
+/// fetch the asset index of `version` into `assets/indexes` and parse it,
+/// the file keeps the bytes as served so it still matches its sha1
+fn fetch_and_store_asset_index(
+    config: &RuntimeConfig,
+    version: &Version,
+    options: &InstallOptions,
+    reporter: &dyn ProgressReporter,
+) -> anyhow::Result<Assets> {
+    let data = fetch_asset_index_data(config, version, options, reporter)?;
+    let assets = serde_json::from_slice(&data)?;
+    let file = asset_index_file(config, &version.asset_index.id);
+    crate::api::write_if_changed(&file, &data)?;
+    Ok(assets)
+}
+
+/// download the objects of `assets` which are missing or corrupt, the legacy
+/// asset directories are left alone
+pub fn install_assets(
+    config: &RuntimeConfig,
+    assets: &Assets,
+    options: &InstallOptions,
+    reporter: &dyn ProgressReporter,
+) -> anyhow::Result<InstallReport> {
+    let pool = TaskPool::new();
+    pool.append(&mut assets_installtask(
+        &config.assets_dir(),
         &config.mirror.assets,
-        &assets,
+        assets,
     ));
+    let downloader = Downloader::new(config)?;
+    pool.install(
+        config.max_concurrent_downloads,
+        &downloader,
+        options,
+        reporter,
+    )
+}
+
+#[test]
+fn test_install_assets() {
+    let dir = crate::TestDir::new("install_assets");
+    let config = RuntimeConfig {
+        game_dir: dir.to_path_buf(),
+        ..Default::default()
+    };
+    let hash = hex::encode(Sha1::digest("asset"));
+    let file = config
+        .game_dir
+        .join("assets/objects")
+        .join(&hash[..2])
+        .join(&hash);
+    fs::create_dir_all(file.parent().unwrap()).unwrap();
+    fs::write(file, "asset").unwrap();
+    let assets: Assets = serde_json::from_value(serde_json::json!({
+        "objects": {"a.ogg": {"hash": hash, "size": 5}}
+    }))
+    .unwrap();
+    let report = install_assets(
+        &config,
+        &assets,
+        &InstallOptions::default(),
+        &progress::SilentReporter,
+    )
+    .unwrap();
+    assert_eq!(report.assets_skipped, 1);
+    assert_eq!(report.assets_downloaded, 0);
+}
+
+/// path of the asset index `id` in the game directory
+fn asset_index_file(config: &RuntimeConfig, id: &str) -> PathBuf {
+    config
+        .assets_dir()
+        .join("indexes")
+        .join(id.to_owned() + ".json")
+}
+
+/// fetch the version json of `version` and return its verified asset index,
+/// which is written into `assets/indexes` with `save`, nothing else is
+/// installed
+/// # Error
+/// Return Error if the asset index has no sha1 or does not match it
+pub fn fetch_asset_index(
+    config: &RuntimeConfig,
+    version: &str,
+    save: bool,
+) -> anyhow::Result<Assets> {
+    // loaders keep the asset index of the game version
+    let config = RuntimeConfig {
+        game_version: version.to_owned(),
+        loader: MCLoader::None,
+        ..config.clone()
+    };
+    let options = InstallOptions::default();
+    let reporter = progress::SilentReporter;
+    let version = fetch_version(&config, None, &options, &reporter)?;
+    if save {
+        fetch_and_store_asset_index(&config, &version, &options, &reporter)
+    } else {
+        fetch_assets(&config, &version, &options, &reporter)
+    }
+}
+
+/// set up the files which are made from the downloaded ones
+fn finish_install(
+    config: &RuntimeConfig,
+    version: &Version,
+    assets: &Assets,
+    reporter: &dyn ProgressReporter,
+) -> anyhow::Result<()> {
+    if let Some(dir) = config.legacy_assets_dir(&version.asset_index.id) {
+        reporter.on_stage("copy legacy assets");
+        copy_legacy_assets(config, &dir, assets)?;
+    }
+    reporter.on_stage("extract natives");
+    extract_natives(config, version)
+}
+
+/// install `config.game_version` from the installed version json, asset
+/// index and files, without any network call
+/// # Error
+/// Return Error if the version is not installed or listing the files which
+/// are missing or corrupt
+fn install_offline(
+    config: &RuntimeConfig,
+    reporter: &dyn ProgressReporter,
+) -> anyhow::Result<InstallReport> {
+    let (version, assets) = installed_version(config)
+        .map_err(|e| anyhow::anyhow!("{} is not installed: {e}", config.game_version))?;
+    let mut report = InstallReport::default();
+    let mut broken = vec![];
+    let tasks = Vec::from(mc_installtask(config, &version, &assets)?);
+    let states = par_map(&tasks, InstallTask::check);
+    for (task, state) in tasks.into_iter().zip(states) {
+        if state != FileState::Ok {
+            broken.push(task.save_file.display().to_string());
+            continue;
+        }
+        match task.r#type {
+            InstallType::Asset => report.assets_skipped += 1,
+            InstallType::Library | InstallType::Native => report.libraries_skipped += 1,
+            InstallType::Client => {}
+        }
+    }
+    if !broken.is_empty() {
+        anyhow::bail!(
+            "files of {} are missing or corrupt, install without --offline to download them:{}",
+            config.game_version,
+            file_list(broken)
+        );
+    }
+    reporter.on_stage("all files are installed, skip downloading");
+    finish_install(config, &version, &assets, reporter)?;
+    Ok(report)
+}
+
+/// join `files` into an indented list of at most 10 lines
+fn file_list(mut files: Vec<String>) -> String {
+    let more = files.len().saturating_sub(10);
+    files.truncate(10);
+    if more > 0 {
+        files.push(format!("and {more} more"));
+    }
+    files.iter().map(|x| format!("\n  - {x}")).collect()
+}
+
+/// files an install run would download
+#[derive(Debug, Default)]
+pub struct InstallPlan {
+    pub tasks: Vec<InstallTask>,
+    /// number of files which are already installed and would be kept
+    pub cached: usize,
+}
+
+impl InstallPlan {
+    /// return the number of tasks of type `r#type`
+    pub fn count(&self, r#type: InstallType) -> usize {
+        self.tasks.iter().filter(|x| x.r#type == r#type).count()
+    }
+
+    /// return the sum of the `size` fields, files without size count as 0
+    pub fn bytes(&self) -> u64 {
+        self.tasks.iter().filter_map(|x| x.size).sum()
+    }
+}
+
+#[test]
+fn test_install_plan() {
+    let task = |r#type, size| InstallTask {
+        r#type,
+        size,
+        ..Default::default()
+    };
+    let plan = InstallPlan {
+        tasks: vec![
+            task(InstallType::Asset, Some(3)),
+            task(InstallType::Asset, Some(4)),
+            task(InstallType::Library, None),
+            task(InstallType::Client, Some(100)),
+        ],
+        cached: 0,
+    };
+    assert_eq!(plan.count(InstallType::Asset), 2);
+    assert_eq!(plan.count(InstallType::Native), 0);
+    assert_eq!(plan.bytes(), 107);
+}
+
+/// resolve the version json and asset index of `config.game_version` and
+/// return the files `install_mc` would download, nothing is written
+pub fn plan_mc(
+    config: &RuntimeConfig,
+    options: &InstallOptions,
+    reporter: &dyn ProgressReporter,
+) -> anyhow::Result<InstallPlan> {
+    let version = fetch_version(config, None, options, reporter)?;
+    let assets = fetch_assets(config, &version, options, reporter)?;
+    let mut plan = InstallPlan::default();
+    for task in mc_installtask(config, &version, &assets)? {
+        if !options.force && task.is_cached() {
+            plan.cached += 1;
+        } else {
+            plan.tasks.push(task);
+        }
+    }
+    Ok(plan)
+}
+
+/// return every install task of a version: assets, libraries, natives and client
+fn mc_installtask(
+    config: &RuntimeConfig,
+    version_json: &Version,
+    asset_json: &Assets,
+) -> anyhow::Result<VecDeque<InstallTask>> {
+    let libraries_dir = config.libraries_dir();
+    let mut tasks = assets_installtask(&config.assets_dir(), &config.mirror.assets, asset_json);
+    let mirrors = LibraryMirrors {
+        libraries: &config.mirror.libraries,
+        fabric_maven: &config.mirror.fabric_maven,
+        forge_maven: &config.mirror.forge_maven,
+        neoforge_maven: &config.mirror.neoforge_maven,
+    };
     tasks.append(&mut libraries_installtask(
-        game_dir,
-        &config.mirror.libraries,
-        &config.mirror.fabric_maven,
-        &version,
+        &libraries_dir,
+        &mirrors,
+        version_json,
     )?);
+    tasks.append(&mut natives_installtask(
+        &libraries_dir,
+        &mirrors,
+        version_json,
+    ));
     tasks.push_back(client_installtask(
-        game_dir,
-        game_version,
+        &config.versions_dir(),
+        &config.game_version,
         &config.mirror.client,
-        &version,
+        version_json,
     )?);
-    tasks.install()?;
+    // assets sharing a hash and libraries listed by both the loader and the
+    // version would be downloaded into the same part file at once
+    let mut seen = HashSet::new();
+    tasks.retain(|x| seen.insert(x.save_file.clone()));
+    Ok(tasks)
+}
 
+#[test]
+fn test_installtask_dedup() {
+    let library = serde_json::json!({
+        "name": "a:a:1",
+        "downloads": {"artifact": {
+            "path": "a/a/1/a-1.jar",
+            "url": "https://libraries.minecraft.net/a/a/1/a-1.jar"
+        }}
+    });
+    let libraries = serde_json::json!([library.clone(), library]);
+    let mut version = crate::api::official::test_version("1.20.4", libraries);
+    version.downloads = Some(serde_json::json!({"client": {
+        "url": "https://piston-data.mojang.com/client.jar",
+        "sha1": "abc"
+    }}));
+    let assets: Assets = serde_json::from_value(serde_json::json!({"objects": {
+        "a.ogg": {"hash": "ab12", "size": 1},
+        "b.ogg": {"hash": "ab12", "size": 1}
+    }}))
+    .unwrap();
+    let config = RuntimeConfig {
+        game_version: "1.20.4".into(),
+        ..Default::default()
+    };
+    let tasks = mc_installtask(&config, &version, &assets).unwrap();
+    let count = |r#type| tasks.iter().filter(|x| x.r#type == r#type).count();
+    assert_eq!(count(InstallType::Asset), 1);
+    assert_eq!(count(InstallType::Library), 1);
+    assert_eq!(count(InstallType::Client), 1);
+}
+
+/// set the url of every task on the fallback mirror of `config.mirror`
+fn set_fallback_urls(
+    config: &RuntimeConfig,
+    version_json: &Version,
+    asset_json: &Assets,
+    tasks: &mut VecDeque<InstallTask>,
+) -> anyhow::Result<()> {
+    let fallback_config = RuntimeConfig {
+        mirror: config.mirror.fallback_mirror(),
+        ..config.clone()
+    };
+    let mut fallback_urls: HashMap<PathBuf, String> =
+        mc_installtask(&fallback_config, version_json, asset_json)?
+            .into_iter()
+            .map(|x| (x.save_file, x.url))
+            .collect();
+    for task in tasks {
+        task.fallback_url = fallback_urls
+            .remove(&task.save_file)
+            .filter(|x| *x != task.url);
+    }
     Ok(())
 }
 
+/// result of checking an installed version against its version json
+#[derive(Debug, Default, Serialize)]
+pub struct VerifyReport {
+    pub ok: usize,
+    pub missing: Vec<PathBuf>,
+    pub mismatched: Vec<PathBuf>,
+}
+
+impl VerifyReport {
+    /// return true if no file is missing or mismatched
+    pub fn is_ok(&self) -> bool {
+        self.missing.is_empty() && self.mismatched.is_empty()
+    }
+}
+
+#[test]
+fn test_install_loader_shared_versions() {
+    let dir = crate::TestDir::new("install_loader_shared_versions");
+    let config = RuntimeConfig {
+        game_dir: dir.join("game"),
+        game_version: "1.20.4".into(),
+        versions_dir: Some(dir.join("minecraft").join("versions")),
+        loader: MCLoader::Fabric("0.15.7".into()),
+        ..Default::default()
+    };
+    let reporter = progress::SilentReporter;
+    let err = install_mc(&config, &InstallOptions::default(), &reporter).unwrap_err();
+    assert!(err
+        .to_string()
+        .contains("may be shared with other launchers"));
+    assert!(!config.versions_dir().exists());
+}
+
+#[test]
+fn test_install_offline() {
+    let dir = crate::TestDir::new("install_offline");
+    let config = RuntimeConfig {
+        game_dir: dir.to_path_buf(),
+        game_version: "1.20.4".into(),
+        ..Default::default()
+    };
+    let version_dir = config.game_dir.join("versions").join("1.20.4");
+    fs::create_dir_all(&version_dir).unwrap();
+    let client_sha1 = hex::encode(Sha1::digest("client"));
+    let mut version = crate::api::official::test_version_json("1.20.4", serde_json::json!([]));
+    version["downloads"] =
+        serde_json::json!({"client": {"url": "https://a/client.jar", "sha1": client_sha1}});
+    fs::write(version_dir.join("1.20.4.json"), version.to_string()).unwrap();
+    fs::write(version_dir.join("1.20.4.jar"), "client").unwrap();
+    let indexes_dir = config.game_dir.join("assets").join("indexes");
+    fs::create_dir_all(&indexes_dir).unwrap();
+    let asset_sha1 = hex::encode(Sha1::digest("icon"));
+    let index = serde_json::json!({"objects": {"icon.png": {"hash": asset_sha1, "size": 4}}});
+    fs::write(indexes_dir.join("12.json"), index.to_string()).unwrap();
+    let options = InstallOptions {
+        offline: true,
+        ..Default::default()
+    };
+    let reporter = progress::SilentReporter;
+    let err = install_mc(&config, &options, &reporter).unwrap_err();
+    assert!(err.to_string().contains(&asset_sha1));
+
+    let objects_dir = config.game_dir.join("assets").join("objects");
+    let object_dir = objects_dir.join(&asset_sha1[0..2]);
+    fs::create_dir_all(&object_dir).unwrap();
+    fs::write(object_dir.join(&asset_sha1), "icon").unwrap();
+    let report = install_mc(&config, &options, &reporter).unwrap();
+    assert_eq!(report.assets_skipped, 1);
+
+    // without offline the installed version is detected and the unreachable
+    // mirror is never asked
+    let mut config = config;
+    config.mirror.version_manifest = "http://127.0.0.1:9/".into();
+    let report = install_mc(&config, &InstallOptions::default(), &reporter).unwrap();
+    assert_eq!(report.assets_skipped, 1);
+}
+
+/// result of checking the asset objects of game_dir against their names
+#[derive(Debug, Default, Serialize)]
+pub struct RepairReport {
+    pub checked: usize,
+    /// files whose sha1 does not match their name, they are downloaded again
+    pub corrupt: Vec<PathBuf>,
+}
+
+/// return the report of `objects_dir` and the hashes of the corrupt files,
+/// which are deleted
+fn remove_corrupt_assets(objects_dir: &Path) -> anyhow::Result<(RepairReport, Vec<String>)> {
+    let mut report = RepairReport::default();
+    let mut hashes = vec![];
+    if !objects_dir.is_dir() {
+        return Ok((report, hashes));
+    }
+    let mut objects = vec![];
+    for entry in WalkDir::new(objects_dir).min_depth(2) {
+        let entry = entry?;
+        let name = entry.file_name().to_string_lossy().into_owned();
+        if !entry.file_type().is_file() || name.ends_with(".part") {
+            continue;
+        }
+        if name.len() != 40 || !name.bytes().all(|x| x.is_ascii_hexdigit()) {
+            warn!("{} is not an asset object, skipped", entry.path().display());
+            continue;
+        }
+        objects.push((entry.into_path(), name));
+    }
+    report.checked = objects.len();
+    let intact = par_map(&objects, |(path, name)| {
+        let in_place = path.parent() == Some(&objects_dir.join(&name[0..2]));
+        Ok::<_, io::Error>(in_place && sha1_file(path)?.eq_ignore_ascii_case(name))
+    });
+    for ((path, name), intact) in objects.into_iter().zip(intact) {
+        if intact? {
+            continue;
+        }
+        fs::remove_file(&path)?;
+        report.corrupt.push(path);
+        hashes.push(name.to_ascii_lowercase());
+    }
+    Ok((report, hashes))
+}
+
+#[test]
+fn test_remove_corrupt_assets() {
+    let dir = crate::TestDir::new("repair_assets");
+    let objects_dir = dir.join("objects");
+    let good = hex::encode(Sha1::digest("good"));
+    let bad = hex::encode(Sha1::digest("bad"));
+    for (hash, data) in [(&good, "good"), (&bad, "tampered")] {
+        fs::create_dir_all(objects_dir.join(&hash[0..2])).unwrap();
+        fs::write(objects_dir.join(&hash[0..2]).join(hash), data).unwrap();
+    }
+    fs::write(objects_dir.join(&good[0..2]).join("notes.txt"), "").unwrap();
+
+    let (report, hashes) = remove_corrupt_assets(&objects_dir).unwrap();
+    assert_eq!(report.checked, 2);
+    assert_eq!(hashes, [bad.as_str()]);
+    assert!(!objects_dir.join(&bad[0..2]).join(&bad).exists());
+    assert!(objects_dir.join(&good[0..2]).join(&good).exists());
+}
+
+/// check every file under `objects` of the assets directory against the
+/// sha1 in its name, and download the corrupt ones again from the assets
+/// mirror, unlike `verify_mc` this covers the objects of every version
+pub fn repair_assets(
+    config: &RuntimeConfig,
+    reporter: &dyn ProgressReporter,
+) -> anyhow::Result<RepairReport> {
+    reporter.on_stage("check assets");
+    let objects_dir = config.assets_dir().join("objects");
+    let (report, hashes) = remove_corrupt_assets(&objects_dir)?;
+    if hashes.is_empty() {
+        return Ok(report);
+    }
+    let fallback = config.mirror.fallback_mirror();
+    let mut tasks: VecDeque<InstallTask> = hashes
+        .into_iter()
+        .map(|hash| {
+            let path = format!("{}/{hash}", &hash[0..2]);
+            let url = config.mirror.assets.clone() + &path;
+            InstallTask {
+                fallback_url: Some(fallback.assets.clone() + &path).filter(|x| *x != url),
+                url,
+                save_file: objects_dir.join(&hash[0..2]).join(&hash),
+                sha1: Some(hash),
+                r#type: InstallType::Asset,
+                size: None,
+            }
+        })
+        .collect();
+    reporter.on_stage("download corrupt assets");
+    let pool = TaskPool::new();
+    pool.append(&mut tasks);
+    let downloader = Downloader::new(config)?;
+    pool.install(
+        config.max_concurrent_downloads,
+        &downloader,
+        &InstallOptions::default(),
+        reporter,
+    )?;
+    Ok(report)
+}
+
+/// read the installed version json and asset index of `config.game_version`
+fn installed_version(config: &RuntimeConfig) -> anyhow::Result<(Version, Assets)> {
+    let version = config.version_api()?;
+    let asset_index_file = asset_index_file(config, &version.asset_index.id);
+    let assets: Assets = serde_json::from_str(&fs::read_to_string(asset_index_file)?)?;
+    Ok((version, assets))
+}
+
+/// check the installed assets, libraries and client of `config.game_version`
+/// against the sha1 recorded in the local version json and asset index, the
+/// files are hashed on one thread per cpu core
+pub fn verify_mc(config: &RuntimeConfig) -> anyhow::Result<VerifyReport> {
+    let (version, assets) = installed_version(config)?;
+    let mut report = VerifyReport::default();
+    let tasks = Vec::from(mc_installtask(config, &version, &assets)?);
+    let states = par_map(&tasks, InstallTask::check);
+    for (task, state) in tasks.into_iter().zip(states) {
+        match state {
+            FileState::Missing => report.missing.push(task.save_file),
+            FileState::Mismatched => report.mismatched.push(task.save_file),
+            FileState::Ok => report.ok += 1,
+        }
+    }
+    Ok(report)
+}
+
+fn natives_installtask(
+    libraries_dir: &Path,
+    mirrors: &LibraryMirrors,
+    version_json: &Version,
+) -> VecDeque<InstallTask> {
+    version_json
+        .libraries
+        .iter()
+        .filter(|x| x.is_target_native())
+        .filter_map(|x| {
+            let Some(artifact) = x.native_artifact() else {
+                warn!("native of library {} not found, skipped", x.name);
+                return None;
+            };
+            Some(InstallTask {
+                url: mirrors.replace(&artifact.url),
+                sha1: artifact.sha1.clone().filter(|x| !x.is_empty()),
+                save_file: libraries_dir.join(&artifact.path),
+                r#type: InstallType::Native,
+                fallback_url: None,
+                size: artifact.size.map(|x| x as u64),
+            })
+        })
+        .collect()
+}
+
+/// make `to` a hard link of `from` if `use_hardlinks`, or a copy if it is
+/// off or the link fails such as across devices, `fs::copy` makes a reflink
+/// on file systems which support them
+fn link_file(from: &Path, to: &Path, use_hardlinks: bool) -> io::Result<()> {
+    if use_hardlinks {
+        match fs::hard_link(from, to) {
+            Ok(()) => return Ok(()),
+            Err(e) => log::debug!("can not link {}: {e}, copy it", to.display()),
+        }
+    }
+    fs::copy(from, to).map(|_| ())
+}
+
+#[test]
+fn test_link_file() {
+    let dir = crate::TestDir::new("link_file");
+    fs::write(dir.join("a"), "asset").unwrap();
+    for use_hardlinks in [true, false] {
+        let _ = fs::remove_file(dir.join("b"));
+        link_file(&dir.join("a"), &dir.join("b"), use_hardlinks).unwrap();
+        assert_eq!(fs::read_to_string(dir.join("b")).unwrap(), "asset");
+    }
+    // a copy does not change with the original
+    fs::write(dir.join("a"), "changed").unwrap();
+    assert_eq!(fs::read_to_string(dir.join("b")).unwrap(), "asset");
+}
+
+/// link the missing assets of `tasks` from the assets directory of other
+/// profiles which have them installed, and return the number of linked files
+fn link_shared_assets(config: &RuntimeConfig, tasks: &VecDeque<InstallTask>) -> usize {
+    let assets_dir = config.assets_dir();
+    let dirs: Vec<PathBuf> = config
+        .profiles
+        .values()
+        .map(|x| config.assets_dir_in(&x.game_dir))
+        .filter(|x| *x != assets_dir)
+        .collect();
+    if dirs.is_empty() {
+        return 0;
+    }
+    let mut linked = 0;
+    for task in tasks.iter().filter(|x| x.r#type == InstallType::Asset) {
+        let Ok(relative) = task.save_file.strip_prefix(&assets_dir) else {
+            continue;
+        };
+        if task.save_file.path_exists() {
+            continue;
+        }
+        let source = dirs.iter().map(|x| x.join(relative)).find(|x| {
+            InstallTask {
+                save_file: x.clone(),
+                ..task.clone()
+            }
+            .is_cached()
+        });
+        let Some(source) = source else {
+            continue;
+        };
+        let result = fs::create_dir_all(task.save_file.parent().unwrap())
+            .and_then(|_| link_file(&source, &task.save_file, config.use_hardlinks));
+        match result {
+            Ok(()) => linked += 1,
+            Err(e) => log::debug!("can not link {}: {e}", source.display()),
+        }
+    }
+    linked
+}
+
+#[test]
+fn test_link_shared_assets() {
+    let dir = crate::TestDir::new("shared_assets");
+    let other = dir.join("other");
+    let object = Path::new("assets").join("objects").join("ab").join("abcd");
+    fs::create_dir_all(other.join(&object).parent().unwrap()).unwrap();
+    fs::write(other.join(&object), "ogg").unwrap();
+    let mut config = RuntimeConfig {
+        game_dir: dir.join("this"),
+        ..Default::default()
+    };
+    config.profiles.insert(
+        "other".into(),
+        crate::config::Instance {
+            game_dir: other,
+            game_version: "1.20.4".into(),
+            loader: MCLoader::None,
+            account: "no_name".into(),
+        },
+    );
+    let task = |hash: &str| InstallTask {
+        sha1: Some(hash.into()),
+        save_file: config.game_dir.join(&object),
+        ..Default::default()
+    };
+    let wrong = VecDeque::from([task("0000")]);
+    assert_eq!(link_shared_assets(&config, &wrong), 0);
+    let tasks = VecDeque::from([task(&hex::encode(Sha1::digest("ogg")))]);
+    assert_eq!(link_shared_assets(&config, &tasks), 1);
+    assert!(tasks[0].is_cached());
+}
+
+/// copy every asset object to `dir`/'original path', hard linked if
+/// `use_hardlinks`
+pub fn copy_legacy_assets(
+    config: &RuntimeConfig,
+    dir: &Path,
+    assets: &Assets,
+) -> anyhow::Result<()> {
+    let objects_dir = config.assets_dir().join("objects");
+    for (name, asset) in &assets.objects {
+        let object = objects_dir.join(&asset.hash[0..2]).join(&asset.hash);
+        let file = dir.join(name);
+        if fs::metadata(&file).is_ok_and(|x| x.len() == asset.size as u64) {
+            continue;
+        }
+        fs::create_dir_all(file.parent().unwrap())?;
+        let _ = fs::remove_file(&file);
+        link_file(&object, &file, config.use_hardlinks)?;
+    }
+    Ok(())
+}
+
+#[test]
+fn test_copy_legacy_assets() {
+    let game_dir = crate::TestDir::new("legacy_assets");
+    let config = RuntimeConfig {
+        game_dir: game_dir.to_path_buf(),
+        ..Default::default()
+    };
+    let objects_dir = config.game_dir.join("assets").join("objects").join("ab");
+    fs::create_dir_all(&objects_dir).unwrap();
+    fs::write(objects_dir.join("abcd"), "ogg").unwrap();
+    let assets: Assets = serde_json::from_str(
+        r#"{"virtual": true, "objects": {"sound/step/grass1.ogg": {"hash": "abcd", "size": 3}}}"#,
+    )
+    .unwrap();
+    let dir = config.legacy_assets_dir("legacy").unwrap();
+    copy_legacy_assets(&config, &dir, &assets).unwrap();
+    let file = dir.join("sound").join("step").join("grass1.ogg");
+    assert_eq!(fs::read_to_string(file).unwrap(), "ogg");
+}
+
+/// extract native libraries into versions/'version'/natives
+/// `META-INF` and the `extract.exclude` entries of the library are skipped
+pub fn extract_natives(config: &RuntimeConfig, version_json: &Version) -> anyhow::Result<()> {
+    let natives_dir = config
+        .versions_dir()
+        .join(&config.game_version)
+        .join("natives");
+    fs::create_dir_all(&natives_dir)?;
+    for lib in version_json
+        .libraries
+        .iter()
+        .filter(|x| x.is_target_native())
+    {
+        let Some(artifact) = lib.native_artifact() else {
+            continue;
+        };
+        let jar = config.libraries_dir().join(&artifact.path);
+        let mut archive = zip::ZipArchive::new(fs::File::open(&jar)?)?;
+        let exclude = lib
+            .extract
+            .as_ref()
+            .map(|x| x.exclude.clone())
+            .unwrap_or_default();
+        for i in 0..archive.len() {
+            let mut entry = archive.by_index(i)?;
+            let Some(name) = entry.enclosed_name() else {
+                continue;
+            };
+            let name_str = name.to_string_lossy();
+            if entry.is_dir()
+                || name_str.starts_with("META-INF")
+                || exclude.iter().any(|x| name_str.starts_with(x.as_str()))
+            {
+                continue;
+            }
+            let file = natives_dir.join(&name);
+            fs::create_dir_all(file.parent().unwrap())?;
+            std::io::copy(&mut entry, &mut fs::File::create(file)?)?;
+        }
+    }
+    Ok(())
+}
+
+/// mirror of each maven repository a library can be downloaded from
+struct LibraryMirrors<'a> {
+    libraries: &'a str,
+    fabric_maven: &'a str,
+    forge_maven: &'a str,
+    neoforge_maven: &'a str,
+}
+
+impl LibraryMirrors<'_> {
+    /// swap the repository of `url` to its mirror, `url` is kept if it is on
+    /// an unknown repository
+    fn replace(&self, url: &str) -> String {
+        let repository = [
+            ("https://libraries.minecraft.net/", self.libraries),
+            ("https://maven.fabricmc.net/", self.fabric_maven),
+            ("https://maven.minecraftforge.net/", self.forge_maven),
+            ("https://maven.neoforged.net/releases/", self.neoforge_maven),
+        ];
+        match repository.into_iter().find(|x| url.starts_with(x.0)) {
+            Some((repository, mirror)) => mirror.to_owned() + &url[repository.len()..],
+            None => url.to_owned(),
+        }
+    }
+}
+
+#[test]
+fn test_library_mirrors() {
+    let mirrors = LibraryMirrors {
+        libraries: "https://bmclapi2.bangbang93.com/maven/",
+        fabric_maven: "https://bmclapi2.bangbang93.com/maven/",
+        forge_maven: "https://bmclapi2.bangbang93.com/maven/",
+        neoforge_maven: "https://bmclapi2.bangbang93.com/maven/",
+    };
+    assert_eq!(
+        mirrors.replace("https://maven.fabricmc.net/net/fabricmc/a.jar"),
+        "https://bmclapi2.bangbang93.com/maven/net/fabricmc/a.jar"
+    );
+    assert_eq!(
+        mirrors.replace("https://maven.neoforged.net/releases/net/neoforged/a.jar"),
+        "https://bmclapi2.bangbang93.com/maven/net/neoforged/a.jar"
+    );
+    let url = "https://repo.spongepowered.org/maven/a.jar";
+    assert_eq!(mirrors.replace(url), url);
+}
+
 fn libraries_installtask(
-    game_dir: &str,
-    libraries_mirror: &str,
-    fabric_maven_mirror: &str,
+    libraries_dir: &Path,
+    mirrors: &LibraryMirrors,
     version_json: &Version,
 ) -> anyhow::Result<VecDeque<InstallTask>> {
     let libraries = &version_json.libraries;
     Ok(libraries
         .iter()
         .filter(|obj| obj.is_target_lib())
-        .map(|x| {
-            let artifact = &x.downloads.artifact;
-            let path = &artifact.path;
-            let mirror;
-            if artifact.url == "https://maven.fabricmc.net/" {
-                mirror = fabric_maven_mirror;
-            }
-            else {
-                mirror = libraries_mirror;
+        .filter_map(|x| {
+            let Some(artifact) = x.artifact() else {
+                warn!("library {} has no downloads.artifact, skipped", x.name);
+                return None;
+            };
+            // libraries without url are extracted from the forge installer
+            if artifact.url.is_empty() {
+                return None;
             }
-            InstallTask {
-                url: mirror.to_owned() + &path,
-                sha1: x.downloads.artifact.sha1.clone(),
-                save_file: Path::new(game_dir).join("libraries").join(&path),
+            Some(InstallTask {
+                url: mirrors.replace(&artifact.url),
+                sha1: artifact.sha1.clone().filter(|x| !x.is_empty()),
+                save_file: libraries_dir.join(&artifact.path),
                 r#type: InstallType::Library,
-            }
+                fallback_url: None,
+                size: artifact.size.map(|x| x as u64),
+            })
         })
         .collect())
 }
 
 fn client_installtask(
-    game_dir: &str,
+    versions_dir: &Path,
     game_version: &str,
     client_mirror: &str,
     version_json: &Version,
 ) -> anyhow::Result<InstallTask> {
-    let json_client = &version_json.downloads["client"];
+    let json_client = version_json
+        .downloads
+        .as_ref()
+        .and_then(|x| x.get("client"))
+        .ok_or_else(|| anyhow::anyhow!("version json of {game_version} has no downloads.client"))?;
+    let url = json_client["url"]
+        .as_str()
+        .ok_or_else(|| anyhow::anyhow!("downloads.client of {game_version} has no url"))?;
+    // a missing sha1 fails the download unless `no_verify`
+    let sha1 = json_client["sha1"].as_str().filter(|x| !x.is_empty());
     Ok(InstallTask {
-        url: json_client["url"]
-            .as_str()
-            .unwrap()
-            .to_string()
-            .replace_domain(client_mirror),
-        sha1: Some(json_client["sha1"].as_str().unwrap().to_string()),
-        save_file: Path::new(game_dir)
-            .join("versions")
+        url: url.to_string().replace_domain(client_mirror),
+        sha1: sha1.map(str::to_owned),
+        save_file: versions_dir
             .join(game_version)
             .join(game_version.to_owned() + ".jar"),
         r#type: InstallType::Client,
+        fallback_url: None,
+        size: json_client["size"].as_u64(),
     })
 }
 
 fn assets_installtask(
-    game_dir: &str,
+    assets_dir: &Path,
     assets_mirror: &str,
     asset_json: &Assets,
 ) -> VecDeque<InstallTask> {
@@ -414,12 +2332,56 @@ fn assets_installtask(
         .map(|x| InstallTask {
             url: assets_mirror.to_owned() + &x.1.hash[0..2] + "/" + &x.1.hash,
             sha1: Some(x.1.hash.clone()),
-            save_file: Path::new(game_dir)
-                .join("assets")
+            save_file: assets_dir
                 .join("objects")
                 .join(&x.1.hash[0..2])
                 .join(x.1.hash.clone()),
             r#type: InstallType::Asset,
+            fallback_url: None,
+            size: Some(x.1.size as u64),
         })
         .collect()
 }
+
+#[test]
+fn test_installtask_mirrors() {
+    let libraries = serde_json::json!([{
+        "name": "a:a:1",
+        "downloads": {"artifact": {
+            "path": "a/a/1/a-1.jar",
+            "url": "https://libraries.minecraft.net/a/a/1/a-1.jar"
+        }}
+    }]);
+    let mut version = crate::api::official::test_version("1.20.4", libraries);
+    version.downloads = Some(serde_json::json!({"client": {
+        "url": "https://piston-data.mojang.com/v1/objects/abc/client.jar",
+        "sha1": "abc"
+    }}));
+    let mirror = crate::config::MCMirror {
+        version_manifest: "https://bmclapi2.bangbang93.com/".into(),
+        assets: "https://bmclapi2.bangbang93.com/assets/".into(),
+        client: "https://bmclapi2.bangbang93.com/".into(),
+        libraries: "https://bmclapi2.bangbang93.com/maven/".into(),
+        ..crate::config::MCMirror::official_mirror()
+    };
+    let config = RuntimeConfig {
+        game_version: "1.20.4".into(),
+        mirror,
+        ..Default::default()
+    };
+    let assets = Assets::default();
+    let mut tasks = mc_installtask(&config, &version, &assets).unwrap();
+    set_fallback_urls(&config, &version, &assets, &mut tasks).unwrap();
+    assert_eq!(
+        tasks[0].url,
+        "https://bmclapi2.bangbang93.com/maven/a/a/1/a-1.jar"
+    );
+    assert_eq!(
+        tasks[1].url,
+        "https://bmclapi2.bangbang93.com/v1/objects/abc/client.jar"
+    );
+    assert_eq!(
+        tasks[0].fallback_url.as_deref(),
+        Some("https://libraries.minecraft.net/a/a/1/a-1.jar")
+    );
+}
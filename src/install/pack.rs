@@ -0,0 +1,390 @@
+/// move an installed version between machines as a single zip archive
+use super::{
+    file_list, install_offline, installed_version, mc_installtask, part_file,
+    progress::ProgressReporter, InstallReport, PathExist,
+};
+use crate::config::RuntimeConfig;
+use serde::{Deserialize, Serialize};
+use sha1::{Digest, Sha1};
+use std::{
+    collections::BTreeSet,
+    fs,
+    io::{self, Read, Write},
+    path::{Component, Path, PathBuf},
+};
+use walkdir::WalkDir;
+
+/// name of the archive entry listing the packed files
+const MANIFEST_NAME: &str = "pack.json";
+
+/// a file of the archive, `path` is relative to game_dir with `/` separators,
+/// files of the assets, libraries and versions directories are under
+/// `assets/`, `libraries/` and `versions/` wherever they are
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PackFile {
+    pub path: String,
+    pub sha1: String,
+    pub size: u64,
+}
+
+/// `pack.json` of an archive made by `export_pack`
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct PackManifest {
+    pub version: String,
+    pub files: Vec<PackFile>,
+}
+
+impl PackManifest {
+    /// return the total size of the packed files
+    pub fn bytes(&self) -> u64 {
+        self.files.iter().map(|x| x.size).sum()
+    }
+}
+
+/// return the directories of `config` in the archive and where they are
+fn pack_dirs(config: &RuntimeConfig) -> [(&'static str, PathBuf); 3] {
+    [
+        ("assets", config.assets_dir()),
+        ("libraries", config.libraries_dir()),
+        ("versions", config.versions_dir()),
+    ]
+}
+
+/// return the path of `file` in the archive with `/` separators
+fn pack_path(config: &RuntimeConfig, file: &Path) -> anyhow::Result<String> {
+    let dirs = pack_dirs(config);
+    let mut parts = vec![];
+    let relative = match dirs.iter().find(|(_, dir)| file.starts_with(dir)) {
+        Some((name, dir)) => {
+            parts.push(name.to_string());
+            file.strip_prefix(dir)?
+        }
+        None => file.strip_prefix(&config.game_dir)?,
+    };
+    parts.extend(
+        relative
+            .components()
+            .map(|x| x.as_os_str().to_string_lossy().into_owned()),
+    );
+    Ok(parts.join("/"))
+}
+
+/// return where `path` of the archive is unpacked to
+fn unpack_path(config: &RuntimeConfig, path: &str) -> PathBuf {
+    pack_dirs(config)
+        .into_iter()
+        .find_map(|(name, dir)| Some(dir.join(path.strip_prefix(name)?.strip_prefix('/')?)))
+        .unwrap_or_else(|| config.game_dir.join(path))
+}
+
+#[test]
+fn test_pack_path() {
+    let config = RuntimeConfig {
+        game_dir: "/games/mclauncher".into(),
+        assets_dir: Some("/home/steve/.minecraft/assets".into()),
+        ..Default::default()
+    };
+    let object = Path::new("/home/steve/.minecraft/assets/objects/ab/abcd");
+    assert_eq!(
+        pack_path(&config, object).unwrap(),
+        "assets/objects/ab/abcd"
+    );
+    assert_eq!(unpack_path(&config, "assets/objects/ab/abcd"), object);
+    let jar = Path::new("/games/mclauncher/versions/1.20.4/1.20.4.jar");
+    assert_eq!(
+        pack_path(&config, jar).unwrap(),
+        "versions/1.20.4/1.20.4.jar"
+    );
+    assert_eq!(unpack_path(&config, "versions/1.20.4/1.20.4.jar"), jar);
+    assert!(pack_path(&config, Path::new("/tmp/a.jar")).is_err());
+}
+
+/// copy `reader` into `writer` and return the sha1 and size of the data
+fn copy_hashed(mut reader: impl Read, writer: &mut impl Write) -> io::Result<(String, u64)> {
+    let mut hasher = Sha1::new();
+    let mut size = 0;
+    let mut buf = [0; 64 * 1024];
+    loop {
+        let len = reader.read(&mut buf)?;
+        if len == 0 {
+            return Ok((hex::encode(hasher.finalize()), size));
+        }
+        hasher.update(&buf[..len]);
+        writer.write_all(&buf[..len])?;
+        size += len as u64;
+    }
+}
+
+/// return the files of installed `config.game_version`: its version
+/// directory without natives, the asset index, the asset objects and the
+/// libraries and natives of this platform
+/// # Error
+/// Return Error listing the files which are missing or corrupt
+fn pack_files(config: &RuntimeConfig) -> anyhow::Result<BTreeSet<PathBuf>> {
+    let (version, assets) = installed_version(config)
+        .map_err(|e| anyhow::anyhow!("{} is not installed: {e}", config.game_version))?;
+    let mut files = BTreeSet::new();
+    let version_dir = config.versions_dir().join(&config.game_version);
+    // natives are extracted again by the install on the other machine
+    for entry in WalkDir::new(&version_dir)
+        .into_iter()
+        .filter_entry(|x| x.path() != version_dir.join("natives"))
+    {
+        let entry = entry?;
+        if entry.file_type().is_file() {
+            files.insert(entry.into_path());
+        }
+    }
+    files.insert(
+        config
+            .assets_dir()
+            .join("indexes")
+            .join(version.asset_index.id.clone() + ".json"),
+    );
+
+    let mut broken = vec![];
+    for task in mc_installtask(config, &version, &assets)? {
+        if !task.save_file.path_exists() || (task.sha1.is_some() && !task.is_cached()) {
+            broken.push(task.save_file.display().to_string());
+        } else {
+            files.insert(task.save_file);
+        }
+    }
+    // libraries without url were extracted from the forge installer
+    for artifact in version
+        .libraries
+        .iter()
+        .filter(|x| x.is_target_lib())
+        .filter_map(|x| x.artifact())
+        .filter(|x| x.url.is_empty())
+    {
+        let file = config.libraries_dir().join(&artifact.path);
+        if file.path_exists() {
+            files.insert(file);
+        } else {
+            broken.push(file.display().to_string());
+        }
+    }
+    if !broken.is_empty() {
+        anyhow::bail!(
+            "files of {} are missing or corrupt, run install to repair them:{}",
+            config.game_version,
+            file_list(broken)
+        );
+    }
+    Ok(files)
+}
+
+/// write installed `config.game_version` with every file it needs into the
+/// zip archive `out`, natives of other platforms are not included
+pub fn export_pack(
+    config: &RuntimeConfig,
+    out: &Path,
+    reporter: &dyn ProgressReporter,
+) -> anyhow::Result<PackManifest> {
+    reporter.on_stage("check installed files");
+    let files = pack_files(config)?;
+    reporter.on_start(files.len() as u64);
+    let part = part_file(out);
+    let write = || -> anyhow::Result<PackManifest> {
+        let mut writer = zip::ZipWriter::new(fs::File::create(&part)?);
+        let mut manifest = PackManifest {
+            version: config.game_version.clone(),
+            files: vec![],
+        };
+        for file in files {
+            let path = pack_path(config, &file)?;
+            let large = fs::metadata(&file)?.len() >= u32::MAX as u64;
+            let options = zip::write::SimpleFileOptions::default().large_file(large);
+            writer.start_file(path.clone(), options)?;
+            let (sha1, size) = copy_hashed(fs::File::open(&file)?, &mut writer)?;
+            reporter.on_file_done(&path, size, false);
+            manifest.files.push(PackFile { path, sha1, size });
+        }
+        writer.start_file(MANIFEST_NAME, zip::write::SimpleFileOptions::default())?;
+        writer.write_all(&serde_json::to_vec_pretty(&manifest)?)?;
+        writer.finish()?;
+        Ok(manifest)
+    };
+    let manifest = write().inspect_err(|_| {
+        let _ = fs::remove_file(&part);
+    })?;
+    reporter.on_finish();
+    fs::rename(&part, out)?;
+    Ok(manifest)
+}
+
+/// return true if `path` of the manifest stays inside game_dir
+fn is_relative(path: &str) -> bool {
+    !path.is_empty()
+        && Path::new(path)
+            .components()
+            .all(|x| matches!(x, Component::Normal(_)))
+}
+
+/// unpack an archive made by `export_pack` into `config.game_dir`, every
+/// file is checked against the sha1 in `pack.json` and the version is then
+/// installed from the unpacked files
+/// # Error
+/// Return Error listing the files which do not match, matching files are
+/// kept
+pub fn import_pack(
+    config: &RuntimeConfig,
+    archive: &Path,
+    reporter: &dyn ProgressReporter,
+) -> anyhow::Result<(PackManifest, InstallReport)> {
+    let name = archive.display();
+    let mut zip = zip::ZipArchive::new(fs::File::open(archive)?)
+        .map_err(|e| anyhow::anyhow!("{name} is not a zip archive: {e}"))?;
+    let manifest: PackManifest = {
+        let entry = zip
+            .by_name(MANIFEST_NAME)
+            .map_err(|_| anyhow::anyhow!("{name} has no {MANIFEST_NAME}, export it again"))?;
+        serde_json::from_reader(entry)?
+    };
+    if !is_relative(&manifest.version) || manifest.version.contains('/') {
+        anyhow::bail!("{name} has an invalid version id {}", manifest.version);
+    }
+    if let Some(file) = manifest.files.iter().find(|x| !is_relative(&x.path)) {
+        anyhow::bail!("{name} has a file outside game_dir: {}", file.path);
+    }
+
+    reporter.on_stage(&format!("unpack {}", manifest.version));
+    reporter.on_start(manifest.files.len() as u64);
+    let mut broken = vec![];
+    for file in &manifest.files {
+        let save_file = unpack_path(config, &file.path);
+        let part = part_file(&save_file);
+        let unpack = |zip: &mut zip::ZipArchive<fs::File>| -> anyhow::Result<()> {
+            let entry = zip
+                .by_name(&file.path)
+                .map_err(|_| anyhow::anyhow!("not in the archive"))?;
+            fs::create_dir_all(save_file.parent().unwrap())?;
+            let (sha1, size) = copy_hashed(entry, &mut fs::File::create(&part)?)?;
+            if size != file.size || !sha1.eq_ignore_ascii_case(&file.sha1) {
+                anyhow::bail!("sha1 mismatch, expected {} got {sha1}", file.sha1);
+            }
+            fs::rename(&part, &save_file)?;
+            Ok(())
+        };
+        match unpack(&mut zip) {
+            Ok(()) => reporter.on_file_done(&file.path, file.size, false),
+            Err(e) => {
+                let _ = fs::remove_file(&part);
+                reporter.on_file_failed(&file.path, &e.to_string());
+                broken.push(format!("{}: {e}", file.path));
+            }
+        }
+    }
+    reporter.on_finish();
+    if !broken.is_empty() {
+        anyhow::bail!(
+            "{} of {} files in {name} are corrupt:{}",
+            broken.len(),
+            manifest.files.len(),
+            file_list(broken)
+        );
+    }
+
+    let config = RuntimeConfig {
+        game_version: manifest.version.clone(),
+        ..config.clone()
+    };
+    let report = install_offline(&config, reporter)?;
+    Ok((manifest, report))
+}
+
+#[cfg(test)]
+fn install_fixture(game_dir: &Path) {
+    let version_dir = game_dir.join("versions").join("1.20.4");
+    fs::create_dir_all(version_dir.join("natives")).unwrap();
+    let libraries = serde_json::json!([{
+        "name": "a:a:1",
+        "downloads": {"artifact": {"path": "a/a/1/a-1.jar", "url": ""}}
+    }]);
+    let mut version = crate::api::official::test_version_json("1.20.4", libraries);
+    version["downloads"] = serde_json::json!({"client": {
+        "url": "https://a/client.jar",
+        "sha1": hex::encode(Sha1::digest("client"))
+    }});
+    fs::write(version_dir.join("1.20.4.json"), version.to_string()).unwrap();
+    fs::write(version_dir.join("1.20.4.jar"), "client").unwrap();
+    fs::write(version_dir.join("natives").join("lwjgl.so"), "native").unwrap();
+    let indexes_dir = game_dir.join("assets").join("indexes");
+    fs::create_dir_all(&indexes_dir).unwrap();
+    let asset_sha1 = hex::encode(Sha1::digest("icon"));
+    let index = serde_json::json!({"objects": {"icon.png": {"hash": asset_sha1, "size": 4}}});
+    fs::write(indexes_dir.join("12.json"), index.to_string()).unwrap();
+    let object_dir = game_dir
+        .join("assets")
+        .join("objects")
+        .join(&asset_sha1[0..2]);
+    fs::create_dir_all(&object_dir).unwrap();
+    fs::write(object_dir.join(&asset_sha1), "icon").unwrap();
+    let library_dir = game_dir.join("libraries").join("a/a/1");
+    fs::create_dir_all(&library_dir).unwrap();
+    fs::write(library_dir.join("a-1.jar"), "library").unwrap();
+}
+
+#[test]
+fn test_export_import_pack() {
+    let dir = crate::TestDir::new("pack");
+    let from = RuntimeConfig {
+        game_dir: dir.join("from"),
+        game_version: "1.20.4".into(),
+        ..Default::default()
+    };
+    install_fixture(&from.game_dir);
+    let archive = dir.join("1.20.4.zip");
+    let reporter = super::progress::SilentReporter;
+    let manifest = export_pack(&from, &archive, &reporter).unwrap();
+    let paths: Vec<_> = manifest.files.iter().map(|x| x.path.as_str()).collect();
+    assert_eq!(paths.len(), 5);
+    assert!(paths.contains(&"libraries/a/a/1/a-1.jar"));
+    assert!(paths.contains(&"assets/indexes/12.json"));
+    assert!(!paths.iter().any(|x| x.contains("natives")));
+
+    let to = RuntimeConfig {
+        game_dir: dir.join("to"),
+        ..Default::default()
+    };
+    let (imported, report) = import_pack(&to, &archive, &reporter).unwrap();
+    assert_eq!(imported.version, "1.20.4");
+    assert_eq!(report.assets_skipped, 1);
+    let jar = to.game_dir.join("versions/1.20.4/1.20.4.jar");
+    assert_eq!(fs::read_to_string(jar).unwrap(), "client");
+}
+
+#[test]
+fn test_import_corrupt_pack() {
+    let dir = crate::TestDir::new("corrupt_pack");
+    let archive = dir.join("pack.zip");
+    let mut writer = zip::ZipWriter::new(fs::File::create(&archive).unwrap());
+    let options = zip::write::SimpleFileOptions::default();
+    writer.start_file("versions/x/x.jar", options).unwrap();
+    writer.write_all(b"tampered").unwrap();
+    let manifest = PackManifest {
+        version: "x".into(),
+        files: vec![PackFile {
+            path: "versions/x/x.jar".into(),
+            sha1: hex::encode(Sha1::digest("client")),
+            size: 8,
+        }],
+    };
+    writer.start_file(MANIFEST_NAME, options).unwrap();
+    writer
+        .write_all(&serde_json::to_vec(&manifest).unwrap())
+        .unwrap();
+    writer.finish().unwrap();
+
+    let config = RuntimeConfig {
+        game_dir: dir.join("game"),
+        ..Default::default()
+    };
+    let reporter = super::progress::SilentReporter;
+    let err = import_pack(&config, &archive, &reporter).unwrap_err();
+    assert!(err.to_string().contains("sha1 mismatch"));
+    assert!(!config.game_dir.join("versions/x/x.jar").exists());
+    assert!(!is_relative("../escape"));
+    assert!(!is_relative("/etc/passwd"));
+}
@@ -0,0 +1,120 @@
+/// report the progress of an install run
+use indicatif::{ProgressBar, ProgressStyle};
+use std::sync::{
+    atomic::{AtomicBool, Ordering},
+    OnceLock,
+};
+
+/// receive the progress of `install_mc`, the file callbacks are called from
+/// the download threads
+pub trait ProgressReporter: Sync {
+    /// a step such as fetching the version json starts
+    fn on_stage(&self, stage: &str);
+    /// downloading `total` files starts
+    fn on_start(&self, total: u64);
+    /// the files of `on_start` have `total` bytes, only called when every
+    /// file has a known size
+    fn on_total_bytes(&self, _total: u64) {}
+    /// file `name` of `bytes` is installed, or kept if `cached`
+    fn on_file_done(&self, name: &str, bytes: u64, cached: bool);
+    /// file `name` failed to install
+    fn on_file_failed(&self, _name: &str, _error: &str) {}
+    /// every file is tried
+    fn on_finish(&self);
+}
+
+/// print the progress to the terminal with a progress bar
+#[derive(Debug, Default)]
+pub struct ConsoleReporter {
+    /// print a line for every installed or cached file
+    pub verbose: bool,
+    bar: OnceLock<ProgressBar>,
+    /// the bar counts bytes instead of files
+    bytes: AtomicBool,
+}
+
+impl ConsoleReporter {
+    pub fn new(verbose: bool) -> Self {
+        ConsoleReporter {
+            verbose,
+            bar: OnceLock::new(),
+            bytes: AtomicBool::new(false),
+        }
+    }
+
+    fn bar(&self) -> &ProgressBar {
+        self.bar.get_or_init(|| {
+            let bar = ProgressBar::new(0);
+            bar.set_style(
+                ProgressStyle::with_template(
+                    "[{elapsed_precise}] {bar:40.cyan/blue} {pos:>7}/{len:7} (eta {eta}) {msg}",
+                )
+                .unwrap()
+                .progress_chars("##-"),
+            );
+            bar
+        })
+    }
+}
+
+impl ProgressReporter for ConsoleReporter {
+    fn on_stage(&self, stage: &str) {
+        println!("{stage}...");
+    }
+
+    fn on_start(&self, total: u64) {
+        self.bar().set_length(total);
+    }
+
+    fn on_total_bytes(&self, total: u64) {
+        let bar = self.bar();
+        bar.set_style(
+            ProgressStyle::with_template(
+                "[{elapsed_precise}] {bar:40.cyan/blue} {bytes:>10}/{total_bytes:10} (eta {eta}) {msg}",
+            )
+            .unwrap()
+            .progress_chars("##-"),
+        );
+        bar.set_length(total);
+        self.bytes.store(true, Ordering::Relaxed);
+    }
+
+    fn on_file_done(&self, name: &str, bytes: u64, cached: bool) {
+        let bar = self.bar();
+        if self.bytes.load(Ordering::Relaxed) {
+            bar.inc(bytes);
+        } else {
+            bar.inc(1);
+        }
+        if cached {
+            bar.set_message("verifying cached files");
+        } else {
+            bar.set_message(format!("{name} installed"));
+        }
+        if self.verbose {
+            let state = if cached { "cached" } else { "installed" };
+            bar.println(format!("{name} {state}"));
+        }
+    }
+
+    fn on_file_failed(&self, _name: &str, _error: &str) {
+        if !self.bytes.load(Ordering::Relaxed) {
+            self.bar().inc(1);
+        }
+    }
+
+    fn on_finish(&self) {
+        self.bar().finish();
+    }
+}
+
+/// ignore the progress, for callers which only need the result
+#[derive(Debug, Default)]
+pub struct SilentReporter;
+
+impl ProgressReporter for SilentReporter {
+    fn on_stage(&self, _stage: &str) {}
+    fn on_start(&self, _total: u64) {}
+    fn on_file_done(&self, _name: &str, _bytes: u64, _cached: bool) {}
+    fn on_finish(&self) {}
+}
@@ -0,0 +1,4 @@
+/// read the installs of other launchers, so their versions and assets can
+/// be used without downloading them again
+pub mod prism;
+pub mod vanilla;
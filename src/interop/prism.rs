@@ -0,0 +1,191 @@
+/// read a prism launcher or multimc instance, from its `instance.cfg` and
+/// the components of `mmc-pack.json`
+use crate::config::{MCLoader, RuntimeConfig};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::HashMap,
+    fs,
+    path::{Path, PathBuf},
+};
+
+/// a component of `mmc-pack.json`, like `net.minecraft` or a loader
+#[derive(Debug, Deserialize)]
+struct Component {
+    uid: String,
+    version: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Pack {
+    components: Vec<Component>,
+}
+
+/// the settings of an instance which map to RuntimeConfig, the unset ones
+/// use the global settings of the launcher
+#[derive(Debug, PartialEq, Serialize)]
+pub struct PrismInstance {
+    pub name: String,
+    pub game_dir: PathBuf,
+    pub game_version: String,
+    pub loader: MCLoader,
+    pub java_path: Option<String>,
+    pub min_memory_size: Option<u32>,
+    pub max_memory_size: Option<u32>,
+    pub jvm_args: Option<Vec<String>>,
+}
+
+/// parse the `key=value` lines of `instance.cfg`, sections and comments are
+/// skipped and quotes around values removed
+fn parse_cfg(text: &str) -> HashMap<&str, &str> {
+    text.lines()
+        .map(str::trim)
+        .filter(|x| !x.starts_with(['[', ';', '#']))
+        .filter_map(|x| x.split_once('='))
+        .map(|(key, value)| {
+            let value = value.trim();
+            let value = value
+                .strip_prefix('"')
+                .and_then(|x| x.strip_suffix('"'))
+                .unwrap_or(value);
+            (key.trim(), value)
+        })
+        .collect()
+}
+
+impl PrismInstance {
+    /// read the instance in `dir`, the directory holding `instance.cfg`
+    /// # Error
+    /// Return Error if a file is missing, or the instance has no minecraft
+    /// component or a loader other than fabric, forge and neoforge
+    pub fn read(dir: &Path) -> anyhow::Result<Self> {
+        let read = |name: &str| {
+            fs::read_to_string(dir.join(name))
+                .map_err(|e| anyhow::anyhow!("can not read {name} of {}: {e}", dir.display()))
+        };
+        let cfg_text = read("instance.cfg")?;
+        let cfg = parse_cfg(&cfg_text);
+        let pack: Pack = serde_json::from_str(&read("mmc-pack.json")?)?;
+
+        let mut game_version = None;
+        let mut loader = MCLoader::None;
+        for component in pack.components {
+            let version = component.version.unwrap_or_default();
+            match component.uid.as_str() {
+                "net.minecraft" => game_version = Some(version),
+                "net.fabricmc.fabric-loader" => loader = MCLoader::Fabric(version),
+                "net.minecraftforge" => loader = MCLoader::Forge(version),
+                "net.neoforged" => loader = MCLoader::NeoForge(version),
+                "org.quiltmc.quilt-loader" | "com.mumfrey.liteloader" => {
+                    anyhow::bail!("loader {} is not supported", component.uid)
+                }
+                // lwjgl and the intermediary mappings come with the version
+                _ => {}
+            }
+        }
+        let game_version = game_version
+            .ok_or_else(|| anyhow::anyhow!("{} has no net.minecraft component", dir.display()))?;
+
+        let overridden = |flags: &[&str]| flags.iter().any(|x| cfg.get(x) == Some(&"true"));
+        let memory = |key| {
+            overridden(&["OverrideMemory"])
+                .then(|| cfg.get(key)?.parse().ok())
+                .flatten()
+        };
+        // older multimc instances keep the game in `minecraft`
+        let game_dir = if !dir.join(".minecraft").is_dir() && dir.join("minecraft").is_dir() {
+            dir.join("minecraft")
+        } else {
+            dir.join(".minecraft")
+        };
+        Ok(PrismInstance {
+            name: cfg.get("name").copied().unwrap_or_default().to_owned(),
+            game_dir,
+            game_version,
+            loader,
+            java_path: overridden(&["OverrideJavaLocation", "OverrideJava"])
+                .then(|| cfg.get("JavaPath"))
+                .flatten()
+                .map(|x| x.to_string()),
+            min_memory_size: memory("MinMemAlloc"),
+            max_memory_size: memory("MaxMemAlloc"),
+            jvm_args: overridden(&["OverrideJavaArgs"])
+                .then(|| cfg.get("JvmArgs"))
+                .flatten()
+                .map(|x| x.split_whitespace().map(str::to_owned).collect()),
+        })
+    }
+
+    /// return `config` with the settings of the instance applied
+    pub fn to_config(&self, config: &RuntimeConfig) -> RuntimeConfig {
+        let mut config = RuntimeConfig {
+            game_dir: self.game_dir.clone(),
+            game_version: self.game_version.clone(),
+            loader: self.loader.clone(),
+            ..config.clone()
+        };
+        if let Some(java_path) = &self.java_path {
+            config.java_path = java_path.clone();
+        }
+        if let Some(min_memory_size) = self.min_memory_size {
+            config.min_memory_size = min_memory_size;
+        }
+        if let Some(max_memory_size) = self.max_memory_size {
+            config.max_memory_size = max_memory_size;
+        }
+        if let Some(jvm_args) = &self.jvm_args {
+            config.jvm_args = jvm_args.clone();
+        }
+        config
+    }
+}
+
+#[test]
+fn test_read_prism_instance() {
+    let dir = crate::TestDir::new("prism_instance");
+    fs::create_dir_all(dir.join(".minecraft")).unwrap();
+    fs::write(
+        dir.join("instance.cfg"),
+        "[General]\nname=Fabulous\nOverrideJavaLocation=true\n\
+         JavaPath=/usr/lib/jvm/java-17/bin/java\nOverrideMemory=true\nMaxMemAlloc=6144\n\
+         MinMemAlloc=512\nOverrideJavaArgs=false\nJvmArgs=\"-XX:+UseZGC\"\n",
+    )
+    .unwrap();
+    let pack = serde_json::json!({
+        "components": [
+            {"uid": "org.lwjgl3", "version": "3.3.1"},
+            {"uid": "net.minecraft", "version": "1.20.1"},
+            {"uid": "net.fabricmc.intermediary", "version": "1.20.1"},
+            {"uid": "net.fabricmc.fabric-loader", "version": "0.14.21"}
+        ],
+        "formatVersion": 1
+    });
+    fs::write(dir.join("mmc-pack.json"), pack.to_string()).unwrap();
+
+    let instance = PrismInstance::read(&dir).unwrap();
+    assert_eq!(instance.name, "Fabulous");
+    assert_eq!(instance.game_dir, dir.join(".minecraft"));
+    assert_eq!(instance.loader, MCLoader::Fabric("0.14.21".into()));
+    assert_eq!(instance.jvm_args, None);
+    let config = instance.to_config(&RuntimeConfig::default());
+    assert_eq!(config.game_version, "1.20.1");
+    assert_eq!(config.java_path, "/usr/lib/jvm/java-17/bin/java");
+    assert_eq!(config.min_memory_size, 512);
+    assert_eq!(config.max_memory_size, 6144);
+
+    let pack = r#"{"components": [{"uid": "net.minecraft", "version": "1.20.1"},
+        {"uid": "net.minecraftforge", "version": "47.1.0"}]}"#;
+    fs::write(dir.join("mmc-pack.json"), pack).unwrap();
+    let instance = PrismInstance::read(&dir).unwrap();
+    assert_eq!(instance.loader, MCLoader::Forge("47.1.0".into()));
+    let pack = r#"{"components": [{"uid": "org.quiltmc.quilt-loader", "version": "0.19.1"}]}"#;
+    fs::write(dir.join("mmc-pack.json"), pack).unwrap();
+    assert!(PrismInstance::read(&dir).is_err());
+}
+
+#[test]
+fn test_parse_cfg() {
+    let cfg = parse_cfg("[General]\n; comment\nname=\"My Pack\"\nJvmArgs=-Xss1M -Dx=y\n");
+    assert_eq!(cfg["name"], "My Pack");
+    assert_eq!(cfg["JvmArgs"], "-Xss1M -Dx=y");
+    assert_eq!(cfg.len(), 2);
+}
@@ -0,0 +1,184 @@
+/// detect the `.minecraft` directory of the official launcher with its
+/// `launcher_profiles.json` and installed versions
+use crate::config::RuntimeConfig;
+use crate::manage::{installed_versions, InstalledVersion};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::BTreeMap,
+    ffi::OsString,
+    fs,
+    path::{Path, PathBuf},
+};
+
+/// resolve the default `.minecraft` with `var` reading environment
+/// variables: `%APPDATA%\.minecraft` on windows,
+/// `~/Library/Application Support/minecraft` on macos and `~/.minecraft`
+/// elsewhere
+fn minecraft_dir_from<F>(var: F) -> Option<PathBuf>
+where
+    F: Fn(&str) -> Option<OsString>,
+{
+    let non_empty = |name| var(name).filter(|x: &OsString| !x.is_empty());
+    if cfg!(target_os = "windows") {
+        return non_empty("APPDATA").map(|x| PathBuf::from(x).join(".minecraft"));
+    }
+    let home = PathBuf::from(non_empty("HOME")?);
+    if cfg!(target_os = "macos") {
+        return Some(home.join("Library/Application Support/minecraft"));
+    }
+    Some(home.join(".minecraft"))
+}
+
+/// return the default game directory of the official launcher
+pub fn minecraft_dir() -> Option<PathBuf> {
+    minecraft_dir_from(|name| std::env::var_os(name))
+}
+
+#[test]
+fn test_minecraft_dir() {
+    let env = |name: &str| (name == "HOME" || name == "APPDATA").then(|| OsString::from("/home/a"));
+    let dir = minecraft_dir_from(env).unwrap();
+    if cfg!(target_os = "macos") {
+        assert_eq!(
+            dir,
+            PathBuf::from("/home/a/Library/Application Support/minecraft")
+        );
+    } else {
+        assert_eq!(dir, PathBuf::from("/home/a/.minecraft"));
+    }
+    assert!(minecraft_dir_from(|_| None).is_none());
+}
+
+/// a profile of `launcher_profiles.json`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct VanillaProfile {
+    #[serde(default)]
+    pub name: String,
+    /// `latest-release`, `latest-snapshot` or `custom`
+    #[serde(default)]
+    pub r#type: String,
+    /// version id, or `latest-release` / `latest-snapshot`
+    pub last_version_id: Option<String>,
+    pub game_dir: Option<PathBuf>,
+    /// path of the java executable
+    pub java_dir: Option<String>,
+    /// jvm arguments in one string
+    pub java_args: Option<String>,
+}
+
+impl VanillaProfile {
+    /// return the version id of the profile in the naming of this launcher,
+    /// where the latest release is `latest`
+    pub fn version(&self) -> Option<&str> {
+        match self.last_version_id.as_deref()? {
+            "latest-release" => Some("latest"),
+            id => Some(id),
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct LauncherProfiles {
+    #[serde(default)]
+    profiles: BTreeMap<String, VanillaProfile>,
+}
+
+/// what the official launcher has in its game directory
+#[derive(Debug, Serialize)]
+pub struct VanillaInstall {
+    pub dir: PathBuf,
+    /// profiles of `launcher_profiles.json` keyed by their id
+    pub profiles: BTreeMap<String, VanillaProfile>,
+    pub versions: Vec<InstalledVersion>,
+}
+
+impl VanillaInstall {
+    /// read the profiles and installed versions of the official launcher in
+    /// `dir`, a missing `launcher_profiles.json` leaves the profiles empty
+    /// # Error
+    /// Return Error if `dir` has neither `launcher_profiles.json` nor
+    /// versions, or the profiles can not be parsed
+    pub fn detect(dir: &Path) -> anyhow::Result<Self> {
+        let profiles_file = dir.join("launcher_profiles.json");
+        let versions_dir = dir.join("versions");
+        if !profiles_file.is_file() && !versions_dir.is_dir() {
+            anyhow::bail!(
+                "{} is not a game directory of the official launcher",
+                dir.display()
+            );
+        }
+        let profiles = match fs::read_to_string(&profiles_file) {
+            Ok(text) => {
+                let profiles: LauncherProfiles = serde_json::from_str(&text).map_err(|e| {
+                    anyhow::anyhow!("can not parse {}: {e}", profiles_file.display())
+                })?;
+                profiles.profiles
+            }
+            Err(_) => BTreeMap::new(),
+        };
+        Ok(VanillaInstall {
+            dir: dir.to_owned(),
+            profiles,
+            versions: installed_versions(&versions_dir)?,
+        })
+    }
+
+    /// point the assets and libraries directories of `config` at the
+    /// official launcher, its files are then shared instead of downloaded
+    /// again, with `versions` also the versions directory so its versions
+    /// can be launched
+    pub fn adopt(&self, config: &mut RuntimeConfig, versions: bool) -> anyhow::Result<()> {
+        let dir = std::path::absolute(&self.dir)?;
+        config.assets_dir = Some(dir.join("assets"));
+        config.libraries_dir = Some(dir.join("libraries"));
+        if versions {
+            config.versions_dir = Some(dir.join("versions"));
+        }
+        Ok(())
+    }
+}
+
+#[test]
+fn test_detect_vanilla() {
+    let root = crate::TestDir::new("detect_vanilla");
+    let dir = root.join(".minecraft");
+    assert!(VanillaInstall::detect(&dir).is_err());
+    let version_dir = dir.join("versions").join("1.20.4");
+    fs::create_dir_all(&version_dir).unwrap();
+    fs::write(version_dir.join("1.20.4.json"), "{}").unwrap();
+    fs::write(version_dir.join("1.20.4.jar"), "").unwrap();
+    let profiles = serde_json::json!({
+        "profiles": {
+            "a1": {"name": "", "type": "latest-release", "lastVersionId": "latest-release"},
+            "b2": {
+                "name": "Modded",
+                "type": "custom",
+                "lastVersionId": "1.20.4",
+                "gameDir": "/games/modded",
+                "javaArgs": "-Xmx4G"
+            }
+        },
+        "settings": {"locale": "en-us"},
+        "version": 3
+    });
+    fs::write(dir.join("launcher_profiles.json"), profiles.to_string()).unwrap();
+
+    let install = VanillaInstall::detect(&dir).unwrap();
+    assert_eq!(install.profiles["a1"].version(), Some("latest"));
+    let modded = &install.profiles["b2"];
+    assert_eq!(modded.version(), Some("1.20.4"));
+    assert_eq!(modded.game_dir.as_deref(), Some(Path::new("/games/modded")));
+    assert_eq!(modded.java_dir, None);
+    assert_eq!(install.versions.len(), 1);
+    assert!(install.versions[0].complete);
+
+    let mut config = RuntimeConfig::default();
+    install.adopt(&mut config, false).unwrap();
+    assert_eq!(config.versions_dir, None);
+    install.adopt(&mut config, true).unwrap();
+    assert_eq!(
+        config.versions_dir().join("1.20.4"),
+        std::path::absolute(&version_dir).unwrap()
+    );
+}
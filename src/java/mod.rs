@@ -0,0 +1,245 @@
+/// find java runtimes installed on this machine
+use crate::api::official::Version;
+use std::{
+    env,
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+#[cfg(target_os = "windows")]
+const JAVA_BIN: &str = "java.exe";
+
+#[cfg(not(target_os = "windows"))]
+const JAVA_BIN: &str = "java";
+
+/// a java executable and its major version
+#[derive(Debug, Clone, PartialEq)]
+pub struct JavaInstall {
+    pub path: PathBuf,
+    pub major: u32,
+}
+
+/// return the java major version a version json requires, versions without
+/// `javaVersion` run on java 8
+pub fn required_java(version: &Version) -> u32 {
+    version.java_version["majorVersion"]
+        .as_u64()
+        .map_or(8, |x| x as u32)
+}
+
+/// return the java major version a release needs by its id, for versions
+/// which are not installed yet: 21 from 1.20.5, 17 from 1.18, 16 for 1.17
+/// and 8 before, `None` for ids which are not a release such as snapshots
+pub fn required_java_by_id(game_version: &str) -> Option<u32> {
+    let release = game_version.split('-').next()?;
+    let mut parts = release.split('.').map(str::parse::<u32>);
+    let (Some(Ok(1)), Some(Ok(minor))) = (parts.next(), parts.next()) else {
+        return None;
+    };
+    let patch = match parts.next() {
+        Some(patch) => patch.ok()?,
+        None => 0,
+    };
+    Some(match (minor, patch) {
+        (21.., _) | (20, 5..) => 21,
+        (18.., _) => 17,
+        (17, _) => 16,
+        _ => 8,
+    })
+}
+
+#[test]
+fn test_required_java_by_id() {
+    assert_eq!(required_java_by_id("1.12.2"), Some(8));
+    assert_eq!(required_java_by_id("1.16.5"), Some(8));
+    assert_eq!(required_java_by_id("1.17.1"), Some(16));
+    assert_eq!(required_java_by_id("1.18"), Some(17));
+    assert_eq!(required_java_by_id("1.20.4"), Some(17));
+    assert_eq!(required_java_by_id("1.20.5-pre1"), Some(21));
+    assert_eq!(required_java_by_id("1.21.1"), Some(21));
+    assert_eq!(required_java_by_id("24w14a"), None);
+    assert_eq!(required_java_by_id("no_game_version"), None);
+}
+
+/// parse the major version from the output of `java -version`, the
+/// `1.x` scheme of java 8 and before is mapped to `x`
+pub fn parse_java_version(output: &str) -> Option<u32> {
+    let start = output.find("version \"")? + "version \"".len();
+    let version = output[start..].split('"').next()?;
+    let mut parts = version.split(['.', '_', '-', '+']);
+    match parts.next()?.parse().ok()? {
+        1 => parts.next()?.parse().ok(),
+        major => Some(major),
+    }
+}
+
+#[test]
+fn test_parse_java_version() {
+    let java8 = "openjdk version \"1.8.0_392\"\nOpenJDK Runtime Environment";
+    assert_eq!(parse_java_version(java8), Some(8));
+    let java17 = "openjdk version \"17.0.9\" 2023-10-17\nOpenJDK Runtime Environment";
+    assert_eq!(parse_java_version(java17), Some(17));
+    assert_eq!(
+        parse_java_version("java version \"21\" 2023-09-19"),
+        Some(21)
+    );
+    assert_eq!(parse_java_version("command not found"), None);
+}
+
+/// resolve `java_path` of the config to an executable file, a bare name such
+/// as `java` is searched in `PATH`
+pub fn resolve_java(java_path: &str) -> Option<PathBuf> {
+    let path = Path::new(java_path);
+    let found = if path.components().count() > 1 {
+        Some(path.to_owned())
+    } else {
+        let dirs = env::var_os("PATH")?;
+        env::split_paths(&dirs)
+            .flat_map(|x| [x.join(java_path), x.join(format!("{java_path}.exe"))])
+            .find(|x| x.is_file())
+    };
+    found.filter(|x| is_executable(x))
+}
+
+#[cfg(unix)]
+fn is_executable(path: &Path) -> bool {
+    use std::os::unix::fs::PermissionsExt;
+    std::fs::metadata(path).is_ok_and(|x| x.is_file() && x.permissions().mode() & 0o111 != 0)
+}
+
+#[cfg(not(unix))]
+fn is_executable(path: &Path) -> bool {
+    path.is_file()
+}
+
+/// run `java -version` and return the major version of the java at `path`
+pub fn java_version(path: &Path) -> anyhow::Result<u32> {
+    let output = Command::new(path)
+        .arg("-version")
+        .output()
+        .map_err(|e| anyhow::anyhow!("can not run {}: {e}", path.display()))?;
+    // java prints its version to stderr
+    let text = String::from_utf8_lossy(&output.stderr);
+    parse_java_version(&text)
+        .ok_or_else(|| anyhow::anyhow!("can not read the version of {}", path.display()))
+}
+
+/// check the java at `path` can run `version`
+/// # Error
+/// Return Error telling the required java if the java is older than the
+/// `javaVersion` of the version json, a newer java only warns
+pub fn check_java(path: &Path, version: &Version) -> anyhow::Result<()> {
+    let required = required_java(version);
+    let major = java_version(path)?;
+    if major < required {
+        anyhow::bail!(
+            "{} requires java {required}, but {} is java {major}, install java {required} \
+             and set java_path in config.toml",
+            version.id,
+            path.display()
+        );
+    }
+    if major > required {
+        log::warn!(
+            "{} is made for java {required}, java {major} may not run it",
+            version.id
+        );
+    }
+    Ok(())
+}
+
+/// return the java executables in `JAVA_HOME`, `PATH` and the default
+/// install directories of the platform
+fn candidates() -> Vec<PathBuf> {
+    let mut paths = vec![];
+    if let Some(home) = env::var_os("JAVA_HOME") {
+        paths.push(Path::new(&home).join("bin").join(JAVA_BIN));
+    }
+    if let Some(path) = env::var_os("PATH") {
+        paths.extend(env::split_paths(&path).map(|x| x.join(JAVA_BIN)));
+    }
+    let install_dirs: &[&str] = match env::consts::OS {
+        "windows" => &[
+            "C:\\Program Files\\Java",
+            "C:\\Program Files\\Eclipse Adoptium",
+            "C:\\Program Files\\Microsoft",
+            "C:\\Program Files\\Zulu",
+        ],
+        "macos" => &["/Library/Java/JavaVirtualMachines"],
+        _ => &["/usr/lib/jvm", "/usr/java", "/opt/java"],
+    };
+    for dir in install_dirs {
+        let Ok(entries) = std::fs::read_dir(dir) else {
+            continue;
+        };
+        for entry in entries.flatten() {
+            let home = entry.path();
+            paths.push(home.join("bin").join(JAVA_BIN));
+            paths.push(home.join("Contents/Home/bin").join(JAVA_BIN));
+        }
+    }
+    let mut unique = vec![];
+    for path in paths {
+        if !unique.contains(&path) {
+            unique.push(path);
+        }
+    }
+    unique
+}
+
+/// pick the java for `required`: the exact major version, then the oldest
+/// newer one, without `required` the newest java
+fn select_java(installs: Vec<JavaInstall>, required: Option<u32>) -> Option<JavaInstall> {
+    match required {
+        Some(required) => installs
+            .iter()
+            .find(|x| x.major == required)
+            .or_else(|| {
+                installs
+                    .iter()
+                    .filter(|x| x.major > required)
+                    .min_by_key(|x| x.major)
+            })
+            .cloned(),
+        None => installs.into_iter().max_by_key(|x| x.major),
+    }
+}
+
+#[test]
+fn test_select_java() {
+    let java = |major| JavaInstall {
+        path: PathBuf::from(format!("java{major}")),
+        major,
+    };
+    let installs = vec![java(8), java(17), java(21)];
+    assert_eq!(select_java(installs.clone(), Some(17)), Some(java(17)));
+    assert_eq!(select_java(installs.clone(), Some(16)), Some(java(17)));
+    assert_eq!(select_java(installs.clone(), None), Some(java(21)));
+    assert_eq!(select_java(installs, Some(25)), None);
+}
+
+/// find the best java runtime for the `required` major version
+/// # Error
+/// Return Error listing the searched paths if no usable java is found
+pub fn detect_java(required: Option<u32>) -> anyhow::Result<JavaInstall> {
+    let paths = candidates();
+    let installs = paths
+        .iter()
+        .filter(|x| x.is_file())
+        .filter_map(|path| {
+            java_version(path).ok().map(|major| JavaInstall {
+                path: path.clone(),
+                major,
+            })
+        })
+        .collect();
+    select_java(installs, required).ok_or_else(|| {
+        let searched: Vec<String> = paths.iter().map(|x| x.display().to_string()).collect();
+        let wanted = required.map_or("any".into(), |x| format!("java {x} or newer"));
+        anyhow::anyhow!(
+            "no usable java found ({wanted}), searched JAVA_HOME, PATH and the default \
+             install directories: [{}]",
+            searched.join(", ")
+        )
+    })
+}
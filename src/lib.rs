@@ -1,5 +1,131 @@
+//! a minecraft launcher as a library, the functions below take every
+//! setting as a parameter and return the result instead of printing it
+//! the http client is shared by the whole process, so `proxy` and
+//! `user_agent` of the first config which makes a request stay in effect
+//! ```no_run
+//! use launcher::config::RuntimeConfig;
+//! let config = RuntimeConfig {
+//!     game_dir: "/tmp/minecraft".into(),
+//!     ..Default::default()
+//! };
+//! let report = launcher::install_version(&config, "1.20.4").unwrap();
+//! println!("{} assets downloaded", report.assets_downloaded);
+//! ```
 pub mod api;
+pub mod auth;
 pub mod config;
 pub mod install;
+pub mod interop;
+pub mod java;
+pub mod logging;
+pub mod manage;
 pub mod mcargument;
 pub mod runtime;
+
+use api::official::{VersionManifest, Versions};
+use config::{RuntimeConfig, VersionType};
+use install::{progress::SilentReporter, InstallOptions, InstallReport, VerifyReport};
+use runtime::LaunchOptions;
+use std::process::{Child, Stdio};
+
+/// install `version_id` with the loader of `config` into `config.game_dir`,
+/// `latest` and `latest-snapshot` are resolved from the version manifest
+/// # Error
+/// Return Error if the install fails, or if an earlier call built the shared
+/// http client with another `proxy` or `user_agent`, they can only be set
+/// once per process
+pub fn install_version(config: &RuntimeConfig, version_id: &str) -> anyhow::Result<InstallReport> {
+    api::ensure_http_client(config.proxy.as_deref(), &config.user_agent)?;
+    let mut config = config.clone();
+    config.game_version = match version_id {
+        "latest" | "latest-snapshot" => config
+            .version_manifest(false)?
+            .resolve(version_id)
+            .to_owned(),
+        _ => version_id.to_owned(),
+    };
+    install::install_mc(&config, &InstallOptions::default(), &SilentReporter)
+}
+
+/// start `config.game_version` and return the game process, its stdout and
+/// stderr are piped for the caller to read
+pub fn launch(config: &RuntimeConfig) -> anyhow::Result<Child> {
+    let mut command = runtime::launch_command(config, &LaunchOptions::default())?;
+    Ok(command
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()?)
+}
+
+/// fetch the version manifest from the mirror of `config` and return the
+/// versions of `version_type`, newest first
+/// # Error
+/// Return Error if the fetch fails, or if the shared http client is built
+/// with another proxy or user agent like `install_version`
+pub fn list_versions(
+    config: &RuntimeConfig,
+    version_type: VersionType,
+) -> anyhow::Result<Vec<Versions>> {
+    api::ensure_http_client(config.proxy.as_deref(), &config.user_agent)?;
+    let manifest = VersionManifest::fetch(&config.mirror.version_manifest)?;
+    Ok(manifest
+        .versions(version_type)
+        .into_iter()
+        .cloned()
+        .collect())
+}
+
+/// check the installed files of `config.game_version` against their sha1
+pub fn verify(config: &RuntimeConfig) -> anyhow::Result<VerifyReport> {
+    install::verify_mc(config)
+}
+
+/// directory of a test under the temp dir, named after the test and the
+/// process so parallel runs and leftovers of crashed ones do not clash, it
+/// starts empty and is removed on drop
+#[cfg(test)]
+pub(crate) struct TestDir(std::path::PathBuf);
+
+#[cfg(test)]
+impl TestDir {
+    pub(crate) fn new(name: &str) -> Self {
+        let path =
+            std::env::temp_dir().join(format!("launcher_test_{name}_{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&path);
+        std::fs::create_dir_all(&path).unwrap();
+        TestDir(path)
+    }
+}
+
+#[cfg(test)]
+impl std::ops::Deref for TestDir {
+    type Target = std::path::Path;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+#[cfg(test)]
+impl AsRef<std::path::Path> for TestDir {
+    fn as_ref(&self) -> &std::path::Path {
+        &self.0
+    }
+}
+
+#[cfg(test)]
+impl Drop for TestDir {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.0);
+    }
+}
+
+#[test]
+fn test_test_dir() {
+    let dir = TestDir::new("test_dir");
+    let path = dir.to_path_buf();
+    std::fs::write(dir.join("a"), "").unwrap();
+    assert!(path.ends_with(format!("launcher_test_test_dir_{}", std::process::id())));
+    drop(dir);
+    assert!(!path.exists());
+}
@@ -0,0 +1,169 @@
+/// log to the console with env_logger and into a size rotated log file
+use log::{LevelFilter, Log, Metadata, Record};
+use std::{
+    fs,
+    io::{self, Write},
+    path::{Path, PathBuf},
+    sync::Mutex,
+};
+
+/// size of the log file before it is rotated
+const MAX_LOG_SIZE: u64 = 5 * 1024 * 1024;
+/// number of rotated log files kept next to the current one
+const KEPT_LOGS: usize = 3;
+
+/// log file which is renamed to `<name>.1` once it would grow over
+/// `max_len`, older files shift up to `<name>.<keep>` and the oldest is
+/// deleted
+pub struct RotatingFile {
+    path: PathBuf,
+    file: fs::File,
+    len: u64,
+    max_len: u64,
+    keep: usize,
+}
+
+impl RotatingFile {
+    /// open `path` for appending, its directory is created
+    pub fn open(path: &Path, max_len: u64, keep: usize) -> io::Result<Self> {
+        if let Some(dir) = path.parent().filter(|x| !x.as_os_str().is_empty()) {
+            fs::create_dir_all(dir)?;
+        }
+        let file = fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)?;
+        Ok(RotatingFile {
+            path: path.to_owned(),
+            len: file.metadata()?.len(),
+            file,
+            max_len,
+            keep: keep.max(1),
+        })
+    }
+
+    fn rotated(&self, n: usize) -> PathBuf {
+        let mut name = self.path.as_os_str().to_owned();
+        name.push(format!(".{n}"));
+        name.into()
+    }
+
+    fn rotate(&mut self) -> io::Result<()> {
+        // rename does not replace files on windows
+        let _ = fs::remove_file(self.rotated(self.keep));
+        for n in (1..self.keep).rev() {
+            let _ = fs::rename(self.rotated(n), self.rotated(n + 1));
+        }
+        fs::rename(&self.path, self.rotated(1))?;
+        self.file = fs::File::create(&self.path)?;
+        self.len = 0;
+        Ok(())
+    }
+
+    /// append `line`, rotating first if the file would grow over `max_len`
+    pub fn write_line(&mut self, line: &str) -> io::Result<()> {
+        let len = line.len() as u64 + 1;
+        if self.len > 0 && self.len + len > self.max_len {
+            self.rotate()?;
+        }
+        writeln!(self.file, "{line}")?;
+        self.len += len;
+        Ok(())
+    }
+}
+
+/// env_logger on the console and the launcher records of at least info
+/// level in the log file, so bug reports have them without `-v`
+struct Logger {
+    console: env_logger::Logger,
+    file: Option<Mutex<RotatingFile>>,
+}
+
+impl Logger {
+    fn to_file(&self, metadata: &Metadata) -> bool {
+        self.file.is_some()
+            && metadata.level() <= LevelFilter::Info.max(self.console.filter())
+            && metadata.target().starts_with("launcher")
+    }
+}
+
+impl Log for Logger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        self.console.enabled(metadata) || self.to_file(metadata)
+    }
+
+    fn log(&self, record: &Record) {
+        self.console.log(record);
+        let Some(file) = &self.file else {
+            return;
+        };
+        if self.console.matches(record) || self.to_file(record.metadata()) {
+            let line = format!(
+                "{} {:5} {}: {}",
+                chrono::Local::now().format("%Y-%m-%d %H:%M:%S"),
+                record.level(),
+                record.target(),
+                record.args()
+            );
+            // a broken log file must not break the launcher
+            let _ = file.lock().unwrap().write_line(&line);
+        }
+    }
+
+    fn flush(&self) {
+        self.console.flush();
+        if let Some(file) = &self.file {
+            let _ = file.lock().unwrap().file.flush();
+        }
+    }
+}
+
+/// log to the console at `level`, or at the level of `RUST_LOG` if set, and
+/// into `log_file` when given, a log file which can not be opened is warned
+/// about and skipped
+pub fn init(level: LevelFilter, log_file: Option<&Path>) {
+    let console = env_logger::Builder::new()
+        .filter_level(level)
+        .parse_default_env()
+        .build();
+    let mut error = None;
+    let file = log_file.and_then(|path| {
+        RotatingFile::open(path, MAX_LOG_SIZE, KEPT_LOGS)
+            .map_err(|e| error = Some(format!("can not open log file {}: {e}", path.display())))
+            .ok()
+    });
+    let max_level = match file {
+        Some(_) => console.filter().max(LevelFilter::Info),
+        None => console.filter(),
+    };
+    let logger = Logger {
+        console,
+        file: file.map(Mutex::new),
+    };
+    if log::set_boxed_logger(Box::new(logger)).is_ok() {
+        log::set_max_level(max_level);
+    }
+    if let Some(error) = error {
+        log::warn!("{error}");
+    }
+}
+
+#[test]
+fn test_rotating_file() {
+    let dir = crate::TestDir::new("rotating_file");
+    let path = dir.join("launcher.log");
+    let mut file = RotatingFile::open(&path, 10, 2).unwrap();
+    for line in ["first", "second", "third", "fourth"] {
+        file.write_line(line).unwrap();
+    }
+    assert_eq!(fs::read_to_string(&path).unwrap(), "fourth\n");
+    assert_eq!(
+        fs::read_to_string(dir.join("launcher.log.1")).unwrap(),
+        "third\n"
+    );
+    assert_eq!(
+        fs::read_to_string(dir.join("launcher.log.2")).unwrap(),
+        "second\n"
+    );
+    assert!(!dir.join("launcher.log.3").exists());
+}
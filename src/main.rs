@@ -1,17 +1,61 @@
+use chrono::{DateTime, Utc};
 use clap::{Parser, Subcommand};
-use launcher::api::official::VersionManifest;
-use launcher::config::{MCMirror, RuntimeConfig, VersionType, MCLoader};
-use launcher::install::install_mc;
-use launcher::runtime::gameruntime;
+use indicatif::HumanBytes;
+use launcher::api::official::{parse_date, VersionManifest};
+use launcher::auth::{self, offline_uuid, DeviceCode};
+use launcher::config::{base_dir, Account, MCLoader, MCMirror, RuntimeConfig, VersionType};
+use launcher::install::pack::{export_pack, import_pack};
+use launcher::install::progress::ConsoleReporter;
+use launcher::install::{
+    download_manifest_file, fetch_asset_index, install_mc, plan_mc, repair_assets, verify_mc,
+    InstallOptions, InstallReport, InstallType, Interrupted, VerifyReport,
+};
+use launcher::interop::prism::PrismInstance;
+use launcher::interop::vanilla::{minecraft_dir, VanillaInstall};
+use launcher::java::{detect_java, required_java, required_java_by_id};
+use launcher::manage::{installed_versions, prune_assets, remove_version};
+use launcher::runtime::{gameruntime, LaunchOptions};
 use launcher::api::fabric::Loader;
-use log::error;
+use log::{error, warn};
 use std::fs;
-use std::path::Path;
-use uuid::Uuid;
+use std::path::{Path, PathBuf};
 
 #[derive(Parser, Debug)]
 #[command(version, about, long_about = None)]
 struct Args {
+    /// Path of the config file, defaults to ./config.toml if it exists, or
+    /// config.toml in $MCLAUNCHER_HOME, $XDG_DATA_HOME/mclauncher or
+    /// ~/.local/share/mclauncher
+    #[arg(long, global = true)]
+    config: Option<PathBuf>,
+
+    /// Print the output of list, installed, verify, repair, asset-index,
+    /// import-vanilla and import-prism as json
+    #[arg(long, global = true)]
+    json: bool,
+
+    /// Fetch the version manifest even if the cached one is fresh
+    #[arg(long, global = true)]
+    refresh: bool,
+
+    /// Log more, -v for info and -vv for debug, RUST_LOG still applies, the
+    /// output of the commands does not change
+    #[arg(short, long, global = true, action = clap::ArgAction::Count)]
+    verbose: u8,
+
+    /// Only log errors
+    #[arg(short, long, global = true, conflicts_with = "verbose")]
+    quiet: bool,
+
+    /// Path of the log file, defaults to logs/launcher.log next to the config
+    /// file, it is rotated at 5 MB
+    #[arg(long, value_name = "PATH", global = true)]
+    log_file: Option<PathBuf>,
+
+    /// Only log to the console
+    #[arg(long, global = true, conflicts_with = "log_file")]
+    no_log_file: bool,
+
     #[command(subcommand)]
     command: Command,
 }
@@ -26,38 +70,236 @@ enum Command {
     #[command(subcommand)]
     List(ListSub),
 
-    /// Change user name
-    Account {
-        name: String,
-    },
+    /// Login with a microsoft account
+    Login,
+
+    /// Manage offline and microsoft accounts
+    #[command(subcommand)]
+    Account(AccountSub),
 
     /// Install Minecraft
     Install {
+        /// Version id, or `latest` / `latest-snapshot` for the newest one
         version: Option<String>,
 
         /// Install fabric loader
         #[arg(long)]
         fabric: Option<String>,
+
+        /// Install forge loader, the build can be `recommended` or `latest`
+        #[arg(long, conflicts_with = "fabric")]
+        forge: Option<String>,
+
+        /// Install neoforge loader, the build can be `latest`
+        #[arg(long, conflicts_with_all = ["fabric", "forge"])]
+        neoforge: Option<String>,
+
+        /// Install optifine standalone from the path or url of its jar
+        #[arg(long, value_name = "JAR", conflicts_with_all = ["fabric", "forge", "neoforge"])]
+        optifine: Option<String>,
+
+        /// Download every file again, even if it is already installed
+        #[arg(long)]
+        force: bool,
+
+        /// Install from the installed files only, fail if any is missing
+        #[arg(long, conflicts_with_all = ["force", "dry_run"])]
+        offline: bool,
+
+        /// Only warn about files which fail to download
+        #[arg(long)]
+        continue_on_error: bool,
+
+        /// Accept files without sha1 in the version json as downloaded, for
+        /// mirrors without checksums, official files are always verified
+        #[arg(long)]
+        no_verify: bool,
+
+        /// Start even if the disk looks too full for the download
+        #[arg(long)]
+        skip_space_check: bool,
+
+        /// Write the downloaded files with the mirror which served them into
+        /// versions/<version>/.download-manifest.json
+        #[arg(long)]
+        download_manifest: bool,
+
+        /// Soft limit of the total download rate in KB/s for this run,
+        /// overrides max_bandwidth of the config
+        #[arg(long, value_name = "KB/s")]
+        max_bandwidth: Option<u64>,
+
+        /// Only print the files which would be downloaded and their total
+        /// size, nothing is written
+        #[arg(long)]
+        dry_run: bool,
+
+        /// Print a line for every installed or cached file, with --dry-run
+        /// the url and save file of every download
+        #[arg(long)]
+        list_files: bool,
     },
 
     /// Running game
-    Run,
+    Run {
+        /// Join a server, as host[:port]
+        #[arg(long, value_name = "ADDR")]
+        server: Option<String>,
+
+        /// Open a singleplayer world, needs 1.20 or newer
+        #[arg(long, value_name = "NAME", conflicts_with = "server")]
+        world: Option<String>,
+
+        /// Launch the demo, for accounts without a license
+        #[arg(long)]
+        demo: bool,
+
+        /// Start in fullscreen
+        #[arg(long)]
+        fullscreen: bool,
+    },
+
+    /// Check the installed files of the game version
+    Verify,
+
+    /// List installed versions, incomplete ones are marked with `!`
+    Installed,
+
+    /// Delete an installed version, its assets and libraries are kept
+    Remove {
+        version: String,
+
+        /// Remove the version even if it is the current game version
+        #[arg(long)]
+        force: bool,
+    },
+
+    /// Delete assets which no installed version uses
+    Prune {
+        /// Only print the assets which would be deleted
+        #[arg(long)]
+        dry_run: bool,
+    },
+
+    /// Pack an installed version with its assets and libraries into a zip
+    Export {
+        version: String,
+
+        /// Path of the archive, defaults to <version>.zip
+        out: Option<PathBuf>,
+    },
+
+    /// Unpack an archive made by export into the game directory
+    Import { archive: PathBuf },
+
+    /// List the profiles and versions of the official launcher
+    ImportVanilla {
+        /// Game directory of the official launcher, defaults to ~/.minecraft
+        /// or its place on windows and macos
+        dir: Option<PathBuf>,
+
+        /// Use the assets and libraries of the official launcher instead of
+        /// downloading them again
+        #[arg(long)]
+        adopt: bool,
+
+        /// Also use the versions of the official launcher, loaders can then
+        /// not be installed and versions not removed
+        #[arg(long, requires = "adopt")]
+        adopt_versions: bool,
+    },
+
+    /// Print the settings of a Prism Launcher or MultiMC instance mapped to
+    /// the config
+    ImportPrism {
+        /// Directory of the instance, which has instance.cfg
+        dir: PathBuf,
+    },
+
+    /// Check every asset object against its sha1 and download corrupt ones
+    /// again, for the assets of all versions
+    Repair,
+
+    /// Fetch and verify only the asset index of a version
+    AssetIndex {
+        /// Version id, defaults to the current game version
+        version: Option<String>,
+
+        /// Write the asset index into assets/indexes
+        #[arg(long)]
+        save: bool,
+    },
 
     /// Set Mirror of minecraft api
     #[command(subcommand)]
     Mirror(Mirrors),
+
+    /// Manage instances with their own game directory, version and account
+    #[command(subcommand)]
+    Profile(ProfileSub),
 }
 
 #[derive(Subcommand, Debug)]
 enum ListSub {
-    #[command(subcommand)]
-    MC(VersionType),
+    MC {
+        #[arg(value_enum, default_value = "all")]
+        r#type: VersionType,
+
+        /// Print at most N versions
+        #[arg(long, value_name = "N")]
+        limit: Option<usize>,
+
+        /// Only print versions whose id contains the text
+        #[arg(long)]
+        contains: Option<String>,
+
+        /// Print the type and release date next to the id
+        #[arg(long)]
+        detailed: bool,
+
+        /// Only print versions released after the date, like 2024-01-01
+        #[arg(long, value_name = "DATE", value_parser = parse_date)]
+        after: Option<DateTime<Utc>>,
+
+        /// Only print versions released before the date
+        #[arg(long, value_name = "DATE", value_parser = parse_date)]
+        before: Option<DateTime<Utc>>,
+    },
     Loader{
         #[command(subcommand)]
         loader: Loaders,
     },
 }
 
+#[derive(Subcommand, Debug)]
+enum AccountSub {
+    /// Add an offline account and select it
+    Add { name: String },
+    /// Remove a saved account
+    Remove { name: String },
+    /// List saved accounts, the selected one is marked with `*`
+    List,
+    /// Select a saved account
+    Select { name: String },
+}
+
+#[derive(Subcommand, Debug)]
+enum ProfileSub {
+    /// Add a profile with the current version and account
+    Create {
+        name: String,
+
+        /// Game directory of the profile, defaults to instances/<name> in the
+        /// launcher directory
+        #[arg(long)]
+        game_dir: Option<PathBuf>,
+    },
+    /// List profiles, the active one is marked with `*`
+    List,
+    /// Switch to a profile, run and install then use its game directory
+    Use { name: String },
+}
+
 #[derive(Subcommand, Debug)]
 enum Loaders {
     Fabric,
@@ -67,44 +309,327 @@ enum Loaders {
 enum Mirrors {
     Official,
     Bmclapi,
+    /// Select the mirror which serves the version manifest fastest
+    Auto,
+    /// Set custom mirror urls, each must be a https url ending with `/`
+    Custom {
+        #[arg(long)]
+        version_manifest: String,
+        #[arg(long)]
+        assets: String,
+        #[arg(long)]
+        client: String,
+        #[arg(long)]
+        libraries: String,
+    },
 }
 
-fn handle_args() -> anyhow::Result<()> {
-    let args = Args::parse();
-    let config_path = Path::new(".").join("config.toml");
+/// stop the install on the first ctrl-c, a second one kills the launcher
+#[cfg(unix)]
+fn handle_ctrl_c() {
+    extern "C" fn on_interrupt(_: libc::c_int) {
+        launcher::install::interrupt();
+        // only async-signal-safe calls are allowed here
+        unsafe { libc::signal(libc::SIGINT, libc::SIG_DFL) };
+    }
+    let handler = on_interrupt as extern "C" fn(libc::c_int) as libc::sighandler_t;
+    unsafe { libc::signal(libc::SIGINT, handler) };
+}
+
+/// ctrl-c kills the launcher at once on other platforms
+#[cfg(not(unix))]
+fn handle_ctrl_c() {}
+
+/// save the active profile and write `config` to `path`
+fn save_config(path: &Path, config: &mut RuntimeConfig) -> anyhow::Result<()> {
+    config.save_profile();
+    fs::write(path, toml::to_string_pretty(config)?)?;
+    Ok(())
+}
+
+/// read and validate the config file at `path`
+fn load_config(path: &Path) -> anyhow::Result<RuntimeConfig> {
+    check_config(path, read_config(path)?)
+}
+
+/// like `load_config`, but create a missing game_dir first, for the commands
+/// which install into it
+fn load_config_creating_game_dir(path: &Path) -> anyhow::Result<RuntimeConfig> {
+    let config = read_config(path)?;
+    fs::create_dir_all(&config.game_dir).map_err(|e| {
+        anyhow::anyhow!("can not create game_dir {}: {e}", config.game_dir.display())
+    })?;
+    check_config(path, config)
+}
+
+/// read the config file at `path` without validating it
+fn read_config(path: &Path) -> anyhow::Result<RuntimeConfig> {
+    let name = path.display();
+    if !path.exists() {
+        anyhow::bail!("{name} not found, run `launcher init` first");
+    }
+    let text = fs::read_to_string(path).map_err(|e| anyhow::anyhow!("can not read {name}: {e}"))?;
+    toml::from_str(&text).map_err(|e| anyhow::anyhow!("{name} is invalid: {e}"))
+}
+
+/// validate `config` read from `path` and build the http client with it
+fn check_config(path: &Path, config: RuntimeConfig) -> anyhow::Result<RuntimeConfig> {
+    let name = path.display();
+    config
+        .validate()
+        .map_err(|e| anyhow::anyhow!("{name}: {e}"))?;
+    launcher::api::init_http_client(config.proxy.as_deref(), &config.user_agent)?;
+    Ok(config)
+}
+
+/// print the files of `report` which are missing or mismatched
+fn print_verify(report: &VerifyReport) {
+    for file in &report.missing {
+        println!("missing: {}", file.display());
+    }
+    for file in &report.mismatched {
+        println!("mismatched: {}", file.display());
+    }
+    println!(
+        "{} ok, {} missing, {} mismatched",
+        report.ok,
+        report.missing.len(),
+        report.mismatched.len()
+    );
+}
+
+/// print a summary table of the install of `config.game_version`
+fn print_install(config: &RuntimeConfig, report: &InstallReport) {
+    let loader = match &config.loader {
+        MCLoader::None => None,
+        MCLoader::Fabric(x) => Some(format!("fabric {x}")),
+        MCLoader::Forge(x) => Some(format!("forge {x}")),
+        MCLoader::NeoForge(x) => Some(format!("neoforge {x}")),
+        MCLoader::OptiFine(x) => Some(format!("optifine {x}")),
+    };
+    let version_dir = config.versions_dir().join(&config.game_version);
+    let path = std::path::absolute(&version_dir).unwrap_or(version_dir);
+    let mut rows = vec![("version", config.game_version.clone())];
+    rows.extend(loader.map(|x| ("loader", x)));
+    rows.push((
+        "assets",
+        format!(
+            "{} downloaded, {} skipped",
+            report.assets_downloaded, report.assets_skipped
+        ),
+    ));
+    rows.push((
+        "libraries",
+        format!(
+            "{} downloaded, {} skipped",
+            report.libraries_downloaded, report.libraries_skipped
+        ),
+    ));
+    if report.failed > 0 {
+        rows.push(("failed", report.failed.to_string()));
+    }
+    rows.push(("downloaded", HumanBytes(report.bytes_total).to_string()));
+    rows.push(("time", format!("{:.1}s", report.duration.as_secs_f64())));
+    rows.push(("path", path.display().to_string()));
+    println!("Installed {}", config.game_version);
+    for (name, value) in rows {
+        println!("  {name:<10} {value}");
+    }
+}
+
+/// return the config file of `--config`, or the default one
+fn config_path(args: &Args) -> PathBuf {
+    args.config.clone().unwrap_or_else(|| {
+        let local = PathBuf::from("config.toml");
+        if local.exists() {
+            local
+        } else {
+            base_dir().join("config.toml")
+        }
+    })
+}
+
+/// log warnings by default, `RUST_LOG` overrides the level of the flags
+fn init_logger(args: &Args) {
+    let level = match (args.quiet, args.verbose) {
+        (true, _) => log::LevelFilter::Error,
+        (_, 0) => log::LevelFilter::Warn,
+        (_, 1) => log::LevelFilter::Info,
+        (_, 2) => log::LevelFilter::Debug,
+        _ => log::LevelFilter::Trace,
+    };
+    let log_file = match &args.log_file {
+        _ if args.no_log_file => None,
+        Some(file) => Some(file.clone()),
+        None => {
+            let config_path = config_path(args);
+            let dir = config_path.parent().unwrap_or(Path::new(""));
+            Some(dir.join("logs").join("launcher.log"))
+        }
+    };
+    launcher::logging::init(level, log_file.as_deref());
+}
+
+fn handle_args(args: Args) -> anyhow::Result<()> {
+    let config_path = config_path(&args);
     let normal_config = RuntimeConfig::default();
     match args.command {
         Command::Init => {
-            fs::write(config_path, toml::to_string_pretty(&normal_config)?)?;
-            println!("Initialized empty game direction");
+            let mut normal_config = normal_config;
+            let required = normal_config
+                .version_api()
+                .map(|x| required_java(&x))
+                .ok()
+                .or_else(|| required_java_by_id(&normal_config.game_version));
+            match detect_java(required) {
+                Ok(java) => {
+                    println!("Found java {} at {}", java.major, java.path.display());
+                    normal_config.java_path = java.path.to_string_lossy().into();
+                }
+                Err(e) => warn!("{e}, set java_path in {}", config_path.display()),
+            }
+            if let Some(dir) = config_path.parent() {
+                fs::create_dir_all(dir)?;
+            }
+            fs::create_dir_all(&normal_config.game_dir)?;
+            fs::write(&config_path, toml::to_string_pretty(&normal_config)?)?;
+            println!(
+                "Initialized empty game direction {}",
+                normal_config.game_dir.display()
+            );
         }
         Command::List(sub) => {
-            let config = fs::read_to_string("config.toml")?;
-            let config: RuntimeConfig = toml::from_str(&config)?;
+            // listing only needs a mirror, so it works before `init`
+            let config = if config_path.exists() {
+                load_config(&config_path)?
+            } else {
+                normal_config
+            };
             match sub {
-                ListSub::MC(_type) => {
-                    let list = VersionManifest::fetch(&config.mirror.version_manifest)?.list(_type);
-                    println!("{:?}",list);
+                ListSub::MC {
+                    r#type,
+                    limit,
+                    contains,
+                    detailed,
+                    after,
+                    before,
+                } => {
+                    let manifest = config.version_manifest(args.refresh)?;
+                    let list: Vec<_> = manifest
+                        .versions(r#type)
+                        .into_iter()
+                        .filter(|x| contains.as_ref().is_none_or(|c| x.id.contains(c.as_str())))
+                        .filter(|x| after.is_none_or(|date| x.released_after(date)))
+                        .filter(|x| before.is_none_or(|date| x.released_before(date)))
+                        .take(limit.unwrap_or(usize::MAX))
+                        .collect();
+                    if args.json {
+                        println!("{}", serde_json::to_string_pretty(&list)?);
+                        return Ok(());
+                    }
+                    let width = list.iter().map(|x| x.id.len()).max().unwrap_or(0);
+                    for version in list {
+                        if detailed {
+                            let date = version.release_time.format("%Y-%m-%d");
+                            println!("{:width$}  {:9}  {date}", version.id, version.r#type);
+                        } else {
+                            println!("{}", version.id);
+                        }
+                    }
                 }
                 ListSub::Loader{loader:_loader} => {
                     let l = Loader::fetch(&config.mirror.fabric_meta)?;
+                    if args.json {
+                        println!("{}", serde_json::to_string_pretty(&l)?);
+                        return Ok(());
+                    }
                     let list:Vec<&str> = l.iter().map(|x|x.version.as_ref()).collect();
                     println!("{:?}",list);
                 }
             }
         }
-        Command::Account { name: _name } => {
-            let config = fs::read_to_string("config.toml")?;
-            let mut config: RuntimeConfig = toml::from_str(&config)?;
-            config.user_name = _name;
-            config.user_uuid = Uuid::new_v4().into();
-            config.user_type = "offline".into();
-            fs::write(config_path, toml::to_string_pretty(&config)?)?;
+        Command::Account(sub) => {
+            let mut config = load_config(&config_path)?;
+            match sub {
+                AccountSub::Add { name } => {
+                    config.select_account(Account {
+                        user_uuid: offline_uuid(&name),
+                        user_name: name,
+                        user_type: "offline".into(),
+                        access_token: String::new(),
+                    });
+                    println!("Set account to {}", config.user_name);
+                }
+                AccountSub::Remove { name } => {
+                    if config.accounts.remove(&name).is_none() {
+                        anyhow::bail!("account {name} not found");
+                    }
+                    println!("Removed account {name}");
+                }
+                AccountSub::List => {
+                    let selected = config.account();
+                    for (name, account) in &config.accounts {
+                        let mark = if *account == selected { "*" } else { " " };
+                        println!("{mark} {name} ({})", account.user_type);
+                    }
+                }
+                AccountSub::Select { name } => {
+                    let account = config
+                        .accounts
+                        .get(&name)
+                        .cloned()
+                        .ok_or_else(|| anyhow::anyhow!("account {name} not found"))?;
+                    config.select_account(account);
+                    println!("Set account to {name}");
+                }
+            }
+            save_config(&config_path, &mut config)?;
+        }
+        Command::Login => {
+            let mut config = load_config(&config_path)?;
+            let code = DeviceCode::fetch(&config.msa_client_id)?;
+            println!("{}", code.message);
+            let ms_token = code.poll(&config.msa_client_id)?;
+            let account = auth::login(&ms_token)?;
+            config.select_account(Account {
+                user_name: account.profile.name,
+                user_type: "msa".into(),
+                user_uuid: account.profile.id,
+                access_token: account.access_token,
+            });
+            save_config(&config_path, &mut config)?;
+            println!("Logged in as {}", config.user_name);
         }
-        Command::Install { version ,fabric } => {
-            let config = fs::read_to_string("config.toml")?;
-            let mut config: RuntimeConfig = toml::from_str(&config)?;
-            if let Some(_version) = version {
+        Command::Install {
+            version,
+            fabric,
+            forge,
+            neoforge,
+            optifine,
+            force,
+            offline,
+            continue_on_error,
+            no_verify,
+            skip_space_check,
+            download_manifest,
+            max_bandwidth,
+            dry_run,
+            list_files,
+        } => {
+            let mut config = load_config_creating_game_dir(&config_path)?;
+            if let Some(mut _version) = version {
+                let latest = matches!(_version.as_str(), "latest" | "latest-snapshot");
+                // installed versions, such as imported ones, need no manifest
+                let json = format!("{_version}/{_version}.json");
+                if latest || !config.versions_dir().join(json).exists() {
+                    let manifest = config.version_manifest(args.refresh)?;
+                    let resolved = manifest.resolve(&_version).to_owned();
+                    if latest {
+                        println!("{_version} resolved to {resolved}");
+                    }
+                    manifest.check(&resolved)?;
+                    _version = resolved;
+                }
                 println!("Set version to {}", &_version);
                 config.game_version = _version;
             }
@@ -112,31 +637,369 @@ fn handle_args() -> anyhow::Result<()> {
                 println!("Set loader to {}", &_fabric);
                 config.loader = MCLoader::Fabric(_fabric);
             }
-            fs::write(config_path, toml::to_string_pretty(&config)?)?;
-            install_mc(&config)?;
+            if let Some(_forge) = forge {
+                println!("Set loader to forge {}", &_forge);
+                config.loader = MCLoader::Forge(_forge);
+            }
+            if let Some(build) = neoforge {
+                println!("Set loader to neoforge {build}");
+                config.loader = MCLoader::NeoForge(build);
+            }
+            if let Some(mut jar) = optifine {
+                // the jar is read again by later installs from other directories
+                if !jar.starts_with("http://") && !jar.starts_with("https://") {
+                    jar = fs::canonicalize(&jar)
+                        .map_err(|e| anyhow::anyhow!("can not read optifine jar {jar}: {e}"))?
+                        .to_string_lossy()
+                        .into_owned();
+                }
+                println!("Set loader to optifine {jar}");
+                config.loader = MCLoader::OptiFine(jar);
+            }
+            let options = InstallOptions {
+                force,
+                continue_on_error,
+                refresh: args.refresh,
+                offline,
+                no_verify,
+                skip_space_check,
+                download_manifest,
+            };
+            if no_verify {
+                warn!(
+                    "--no-verify: files without sha1 are installed unverified and may be corrupt"
+                );
+            }
+            let reporter = ConsoleReporter::new(list_files);
+            if dry_run {
+                let plan = plan_mc(&config, &options, &reporter)?;
+                if list_files {
+                    for task in &plan.tasks {
+                        println!("{} -> {}", task.url, task.save_file.display());
+                    }
+                }
+                for (name, r#type) in [
+                    ("assets", InstallType::Asset),
+                    ("libraries", InstallType::Library),
+                    ("natives", InstallType::Native),
+                    ("client", InstallType::Client),
+                ] {
+                    println!("{name}: {}", plan.count(r#type));
+                }
+                println!(
+                    "{} files, {} bytes would be downloaded, {} already installed",
+                    plan.tasks.len(),
+                    plan.bytes(),
+                    plan.cached
+                );
+                return Ok(());
+            }
+            save_config(&config_path, &mut config)?;
+            if let Some(max_bandwidth) = max_bandwidth {
+                config.max_bandwidth = max_bandwidth;
+            }
+            handle_ctrl_c();
+            let report = install_mc(&config, &options, &reporter)?;
+            print_install(&config, &report);
+            if download_manifest && !report.downloads.is_empty() {
+                let file = download_manifest_file(&config);
+                println!("Wrote the download manifest to {}", file.display());
+            }
+        }
+        Command::Run {
+            server,
+            world,
+            demo,
+            fullscreen,
+        } => {
+            let config = load_config(&config_path)?;
+            let options = LaunchOptions {
+                server,
+                world,
+                demo,
+                fullscreen,
+            };
+            gameruntime(config, &options)?;
+        }
+        Command::Verify => {
+            let config = load_config(&config_path)?;
+            let report = verify_mc(&config)?;
+            if args.json {
+                println!("{}", serde_json::to_string_pretty(&report)?);
+            } else {
+                print_verify(&report);
+            }
+            if !report.is_ok() {
+                anyhow::bail!("{} is not installed correctly", config.game_version);
+            }
+        }
+        Command::Installed => {
+            let config = load_config(&config_path)?;
+            let versions = installed_versions(&config.versions_dir())?;
+            if args.json {
+                println!("{}", serde_json::to_string_pretty(&versions)?);
+                return Ok(());
+            }
+            for version in versions {
+                let mark = if version.complete { " " } else { "!" };
+                println!("{mark} {}", version.id);
+            }
+        }
+        Command::Remove { version, force } => {
+            let config = load_config(&config_path)?;
+            if version == config.game_version && !force {
+                anyhow::bail!("{version} is the current game version, pass --force to remove it");
+            }
+            // a shared versions directory belongs to the other launcher
+            if config
+                .versions_dir
+                .as_deref()
+                .is_some_and(Path::is_absolute)
+            {
+                anyhow::bail!(
+                    "versions_dir {} may be shared with other launchers, remove {version} there",
+                    config.versions_dir().display()
+                );
+            }
+            let freed = remove_version(&config.versions_dir(), &version)?;
+            println!("Removed {version}, {freed} bytes freed");
+            if !installed_versions(&config.versions_dir())?.is_empty() {
+                println!("assets are shared between versions and are kept, run prune to delete unused ones");
+            }
+        }
+        Command::Prune { dry_run } => {
+            let config = load_config(&config_path)?;
+            // a shared assets directory has objects of versions unknown here
+            if config.assets_dir.as_deref().is_some_and(Path::is_absolute) {
+                anyhow::bail!(
+                    "assets_dir {} may be shared with other launchers, prune it there",
+                    config.assets_dir().display()
+                );
+            }
+            let report = prune_assets(&config.versions_dir(), &config.assets_dir(), dry_run)?;
+            for file in &report.files {
+                println!("{}", file.display());
+            }
+            let action = if dry_run { "would be freed" } else { "freed" };
+            println!(
+                "{} assets, {} bytes {action}",
+                report.files.len(),
+                report.bytes
+            );
+        }
+        Command::Export { version, out } => {
+            let config = RuntimeConfig {
+                game_version: version,
+                ..load_config(&config_path)?
+            };
+            let out = out.unwrap_or_else(|| PathBuf::from(config.game_version.clone() + ".zip"));
+            let manifest = export_pack(&config, &out, &ConsoleReporter::default())?;
+            println!(
+                "Exported {} to {}: {} files, {} MB",
+                manifest.version,
+                out.display(),
+                manifest.files.len(),
+                manifest.bytes() / (1024 * 1024)
+            );
+        }
+        Command::Import { archive } => {
+            let config = load_config_creating_game_dir(&config_path)?;
+            let (manifest, _) = import_pack(&config, &archive, &ConsoleReporter::default())?;
+            println!(
+                "Imported {}: {} files, {} MB, run `install {}` to select it",
+                manifest.version,
+                manifest.files.len(),
+                manifest.bytes() / (1024 * 1024),
+                manifest.version
+            );
         }
-        Command::Run => {
-            let config = fs::read_to_string("config.toml")?;
-            let config: RuntimeConfig = toml::from_str(&config)?;
-            gameruntime(config)?;
+        Command::ImportVanilla {
+            dir,
+            adopt,
+            adopt_versions,
+        } => {
+            let mut config = load_config(&config_path)?;
+            let dir = dir.or_else(minecraft_dir).ok_or_else(|| {
+                anyhow::anyhow!("can not find the official launcher, pass its game directory")
+            })?;
+            let install = VanillaInstall::detect(&dir)?;
+            if args.json {
+                println!("{}", serde_json::to_string_pretty(&install)?);
+            } else {
+                println!("Official launcher in {}", dir.display());
+                for profile in install.profiles.values() {
+                    let name = match profile.name.as_str() {
+                        "" => &profile.r#type,
+                        name => name,
+                    };
+                    let version = profile.version().unwrap_or("no version");
+                    match &profile.game_dir {
+                        Some(game_dir) => {
+                            println!("  profile {name}: {version} in {}", game_dir.display())
+                        }
+                        None => println!("  profile {name}: {version}"),
+                    }
+                }
+                for version in &install.versions {
+                    let mark = if version.complete { " " } else { "!" };
+                    println!("{mark} {}", version.id);
+                }
+            }
+            if adopt {
+                install.adopt(&mut config, adopt_versions)?;
+                save_config(&config_path, &mut config)?;
+                if !args.json {
+                    let what = if adopt_versions {
+                        "assets, libraries and versions"
+                    } else {
+                        "assets and libraries"
+                    };
+                    println!("Using the {what} of {}", dir.display());
+                }
+            } else if !args.json {
+                println!("run with --adopt to use them instead of downloading them again");
+            }
+        }
+        Command::ImportPrism { dir } => {
+            let instance = PrismInstance::read(&dir)?;
+            if args.json {
+                println!("{}", serde_json::to_string_pretty(&instance)?);
+                return Ok(());
+            }
+            println!(
+                "# settings of {}, copy them into config.toml",
+                dir.display()
+            );
+            print!("{}", toml::to_string_pretty(&instance)?);
+        }
+        Command::Repair => {
+            let config = load_config(&config_path)?;
+            let report = repair_assets(&config, &ConsoleReporter::default())?;
+            if args.json {
+                println!("{}", serde_json::to_string_pretty(&report)?);
+                return Ok(());
+            }
+            for file in &report.corrupt {
+                println!("repaired: {}", file.display());
+            }
+            println!(
+                "{} assets checked, {} repaired",
+                report.checked,
+                report.corrupt.len()
+            );
+        }
+        Command::AssetIndex { version, save } => {
+            let config = load_config(&config_path)?;
+            let version = version.unwrap_or_else(|| config.game_version.clone());
+            let assets = fetch_asset_index(&config, &version, save)?;
+            if args.json {
+                println!("{}", serde_json::to_string_pretty(&assets)?);
+                return Ok(());
+            }
+            let bytes: u64 = assets.objects.values().map(|x| x.size as u64).sum();
+            println!(
+                "asset index of {version}: {} objects, {bytes} bytes",
+                assets.objects.len()
+            );
         }
         Command::Mirror(mirror) => {
-            let config = fs::read_to_string("config.toml")?;
-            let mut config: RuntimeConfig = toml::from_str(&config)?;
-            match mirror {
-                Mirrors::Official => config.mirror = MCMirror::official_mirror(),
-                Mirrors::Bmclapi => config.mirror = MCMirror::bmcl_mirror(),
+            let mut config = load_config(&config_path)?;
+            let name = match mirror {
+                Mirrors::Official => {
+                    config.mirror = MCMirror::official_mirror();
+                    "official"
+                }
+                Mirrors::Bmclapi => {
+                    config.mirror = MCMirror::bmcl_mirror();
+                    "bmclapi"
+                }
+                Mirrors::Auto => {
+                    let mirrors = [
+                        ("official", MCMirror::official_mirror()),
+                        ("bmclapi", MCMirror::bmcl_mirror()),
+                    ];
+                    let (_, name, mirror) = mirrors
+                        .into_iter()
+                        .filter_map(|(name, mirror)| {
+                            match VersionManifest::benchmark(&mirror.version_manifest) {
+                                Ok(latency) => {
+                                    println!("{name}: {} ms", latency.as_millis());
+                                    Some((latency, name, mirror))
+                                }
+                                Err(e) => {
+                                    println!("{name}: unreachable, {e}");
+                                    None
+                                }
+                            }
+                        })
+                        .min_by_key(|x| x.0)
+                        .ok_or_else(|| anyhow::anyhow!("no mirror is reachable"))?;
+                    config.mirror = mirror;
+                    name
+                }
+                Mirrors::Custom {
+                    version_manifest,
+                    assets,
+                    client,
+                    libraries,
+                } => {
+                    let mirror = MCMirror {
+                        version_manifest,
+                        assets,
+                        client,
+                        libraries,
+                        ..config.mirror
+                    };
+                    mirror.validate()?;
+                    config.mirror = mirror;
+                    "custom"
+                }
+            };
+            save_config(&config_path, &mut config)?;
+            println!("Set {name} mirror");
+        }
+        Command::Profile(sub) => {
+            let mut config = load_config(&config_path)?;
+            match sub {
+                ProfileSub::Create { name, game_dir } => {
+                    let game_dir =
+                        game_dir.unwrap_or_else(|| base_dir().join("instances").join(&name));
+                    fs::create_dir_all(&game_dir)?;
+                    config.create_profile(&name, game_dir.clone())?;
+                    println!("Created profile {name} in {}", game_dir.display());
+                }
+                ProfileSub::List => {
+                    for (name, instance) in &config.profiles {
+                        let mark = if config.profile.as_ref() == Some(name) {
+                            "*"
+                        } else {
+                            " "
+                        };
+                        println!(
+                            "{mark} {name} ({}, {})",
+                            instance.game_version,
+                            instance.game_dir.display()
+                        );
+                    }
+                }
+                ProfileSub::Use { name } => {
+                    config.use_profile(&name)?;
+                    println!("Switched to profile {name}");
+                }
             }
-            fs::write(config_path, toml::to_string_pretty(&config)?)?;
-            println!("Set official mirror");
+            save_config(&config_path, &mut config)?;
         }
     }
     Ok(())
 }
 
 fn main() {
-    env_logger::init();
-    if let Err(e) = handle_args() {
-        error!("{:#?}", e);
+    let args = Args::parse();
+    init_logger(&args);
+    if let Err(e) = handle_args(args) {
+        error!("{:#}", e);
+        // 130 is the exit code of a shell command ended by ctrl-c
+        let code = if e.is::<Interrupted>() { 130 } else { 1 };
+        std::process::exit(code);
     }
 }
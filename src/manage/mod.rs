@@ -0,0 +1,193 @@
+/// manage the versions installed in game_dir
+use crate::api::official::{Assets, Version};
+use serde::Serialize;
+use std::{
+    collections::HashSet,
+    fs,
+    path::{Component, Path, PathBuf},
+};
+use walkdir::WalkDir;
+
+/// a directory of the versions directory
+#[derive(Debug, PartialEq, Serialize)]
+pub struct InstalledVersion {
+    pub id: String,
+    /// true if both `<id>.json` and `<id>.jar` exist
+    pub complete: bool,
+}
+
+/// scan `versions_dir` for installed versions, sorted by id
+pub fn installed_versions(versions_dir: &Path) -> anyhow::Result<Vec<InstalledVersion>> {
+    if !versions_dir.is_dir() {
+        return Ok(vec![]);
+    }
+    let mut versions = vec![];
+    for entry in fs::read_dir(versions_dir)? {
+        let entry = entry?;
+        if !entry.file_type()?.is_dir() {
+            continue;
+        }
+        let id = entry.file_name().to_string_lossy().into_owned();
+        let dir = entry.path();
+        let complete =
+            dir.join(id.clone() + ".json").is_file() && dir.join(id.clone() + ".jar").is_file();
+        versions.push(InstalledVersion { id, complete });
+    }
+    versions.sort_by(|a, b| a.id.cmp(&b.id));
+    Ok(versions)
+}
+
+/// return the total size of files under `dir`
+fn dir_size(dir: &Path) -> anyhow::Result<u64> {
+    let mut size = 0;
+    for entry in WalkDir::new(dir) {
+        let entry = entry?;
+        if entry.file_type().is_file() {
+            size += entry.metadata()?.len();
+        }
+    }
+    Ok(size)
+}
+
+/// delete `<id>` of `versions_dir` and return the bytes freed
+/// assets and libraries are shared between versions and are kept
+pub fn remove_version(versions_dir: &Path, id: &str) -> anyhow::Result<u64> {
+    let dir = versions_dir.join(id);
+    // the id must name a directory right under versions_dir
+    let mut components = Path::new(id).components();
+    let is_name =
+        matches!(components.next(), Some(Component::Normal(_))) && components.next().is_none();
+    if !is_name || !dir.is_dir() {
+        anyhow::bail!("version {id} is not installed");
+    }
+    let size = dir_size(&dir)?;
+    fs::remove_dir_all(dir)?;
+    Ok(size)
+}
+
+/// asset objects which no installed version references
+#[derive(Debug, Default)]
+pub struct PruneReport {
+    pub files: Vec<PathBuf>,
+    pub bytes: u64,
+}
+
+/// return the asset hashes referenced by the asset index of installed
+/// version `id`
+fn referenced_assets(
+    versions_dir: &Path,
+    assets_dir: &Path,
+    id: &str,
+) -> anyhow::Result<HashSet<String>> {
+    let version_file = versions_dir.join(id).join(id.to_owned() + ".json");
+    let version: Version = serde_json::from_str(&fs::read_to_string(version_file)?)?;
+    let index_file = assets_dir
+        .join("indexes")
+        .join(version.asset_index.id + ".json");
+    let assets: Assets = serde_json::from_str(&fs::read_to_string(index_file)?)?;
+    Ok(assets.objects.into_values().map(|x| x.hash).collect())
+}
+
+/// delete the files under `objects` of `assets_dir` which no version of
+/// `versions_dir` references, only report them if `dry_run`
+/// # Error
+/// Return Error if the asset index of an installed version can not be read,
+/// nothing is deleted then
+pub fn prune_assets(
+    versions_dir: &Path,
+    assets_dir: &Path,
+    dry_run: bool,
+) -> anyhow::Result<PruneReport> {
+    let mut referenced = HashSet::new();
+    for version in installed_versions(versions_dir)? {
+        let hashes = referenced_assets(versions_dir, assets_dir, &version.id)
+            .map_err(|e| anyhow::anyhow!("can not read the asset index of {}: {e}", version.id))?;
+        referenced.extend(hashes);
+    }
+
+    let mut report = PruneReport::default();
+    let objects_dir = assets_dir.join("objects");
+    if !objects_dir.is_dir() {
+        return Ok(report);
+    }
+    for entry in WalkDir::new(objects_dir) {
+        let entry = entry?;
+        let name = entry.file_name().to_string_lossy();
+        if !entry.file_type().is_file() || referenced.contains(name.as_ref()) {
+            continue;
+        }
+        report.bytes += entry.metadata()?.len();
+        if !dry_run {
+            fs::remove_file(entry.path())?;
+        }
+        report.files.push(entry.into_path());
+    }
+    Ok(report)
+}
+
+#[test]
+fn test_installed_versions() {
+    let game_dir = crate::TestDir::new("installed_versions");
+    let complete = game_dir.join("versions").join("1.20.4");
+    fs::create_dir_all(&complete).unwrap();
+    fs::write(complete.join("1.20.4.json"), "{}").unwrap();
+    fs::write(complete.join("1.20.4.jar"), "").unwrap();
+    let broken = game_dir.join("versions").join("1.19");
+    fs::create_dir_all(&broken).unwrap();
+    fs::write(broken.join("1.19.json"), "{}").unwrap();
+
+    let versions = installed_versions(&game_dir.join("versions")).unwrap();
+    assert_eq!(
+        versions,
+        [
+            InstalledVersion {
+                id: "1.19".into(),
+                complete: false
+            },
+            InstalledVersion {
+                id: "1.20.4".into(),
+                complete: true
+            },
+        ]
+    );
+}
+
+#[test]
+fn test_remove_version() {
+    let game_dir = crate::TestDir::new("remove_version");
+    let dir = game_dir.join("versions").join("24w03a");
+    fs::create_dir_all(dir.join("natives")).unwrap();
+    fs::write(dir.join("24w03a.json"), "{}").unwrap();
+    fs::write(dir.join("natives").join("lwjgl.so"), "native").unwrap();
+    let versions_dir = game_dir.join("versions");
+    assert_eq!(remove_version(&versions_dir, "24w03a").unwrap(), 8);
+    assert!(!dir.exists());
+    assert!(remove_version(&versions_dir, "24w03a").is_err());
+    assert!(remove_version(&versions_dir, "..").is_err());
+}
+
+#[test]
+fn test_prune_assets() {
+    let game_dir = crate::TestDir::new("prune_assets");
+    let version_dir = game_dir.join("versions").join("1.20.4");
+    fs::create_dir_all(&version_dir).unwrap();
+    let version = crate::api::official::test_version_json("1.20.4", serde_json::json!([]));
+    fs::write(version_dir.join("1.20.4.json"), version.to_string()).unwrap();
+    let indexes_dir = game_dir.join("assets").join("indexes");
+    fs::create_dir_all(&indexes_dir).unwrap();
+    let index = r#"{"objects": {"icon.png": {"hash": "aa11", "size": 4}}}"#;
+    fs::write(indexes_dir.join("12.json"), index).unwrap();
+    let objects_dir = game_dir.join("assets").join("objects").join("aa");
+    fs::create_dir_all(&objects_dir).unwrap();
+    fs::write(objects_dir.join("aa11"), "used").unwrap();
+    fs::write(objects_dir.join("aa22"), "orphan").unwrap();
+
+    let (versions_dir, assets_dir) = (game_dir.join("versions"), game_dir.join("assets"));
+    let report = prune_assets(&versions_dir, &assets_dir, true).unwrap();
+    assert_eq!(report.files, [objects_dir.join("aa22")]);
+    assert_eq!(report.bytes, 6);
+    assert!(objects_dir.join("aa22").exists());
+    prune_assets(&versions_dir, &assets_dir, false).unwrap();
+    assert!(!objects_dir.join("aa22").exists());
+    assert!(objects_dir.join("aa11").exists());
+}
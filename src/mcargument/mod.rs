@@ -1,140 +1,386 @@
-use crate::api::official::Version;
+use crate::api::official::{rules_match, Library, Rules, Version};
+use crate::auth::offline_uuid;
 use crate::config::RuntimeConfig;
 use regex::Regex;
-use std::{collections::HashMap, fs, path::Path};
+use std::{cmp::Ordering, collections::HashMap, fs, path::PathBuf};
 
 #[cfg(target_os = "windows")]
 const CLASSPATH_SEPARATOR: &str = ";";
 
-#[cfg(target_os = "linux")]
+#[cfg(not(target_os = "windows"))]
 const CLASSPATH_SEPARATOR: &str = ":";
 
-#[cfg(target_os = "macos")]
-const CLASSPATH_SEPARATOR: &str = ":";
+/// compare two library versions by their numeric and text parts, like
+/// `1.10.2` after `1.9` and `33.0.0-jre` after `32.1.2-jre`
+fn compare_versions(a: &str, b: &str) -> Ordering {
+    let parts = |x: &str| -> Vec<String> {
+        x.split(['.', '-', '_', '+'])
+            .map(|x| x.to_owned())
+            .collect()
+    };
+    let (a, b) = (parts(a), parts(b));
+    for (a, b) in a.iter().zip(&b) {
+        let order = match (a.parse::<u64>(), b.parse::<u64>()) {
+            (Ok(a), Ok(b)) => a.cmp(&b),
+            _ => a.cmp(b),
+        };
+        if order.is_ne() {
+            return order;
+        }
+    }
+    a.len().cmp(&b.len())
+}
 
-fn replace_arguments(args: Vec<String>, valuemap: HashMap<&str, String>) -> Vec<String> {
-    let regex = Regex::new(r"(?<replace>\$\{\S+\})").unwrap();
-    args.iter()
-        .map(|x| {
-            if let Some(c) = regex.captures(x.as_str()) {
-                if let Some(content) = valuemap.get(&c["replace"]) {
-                    return x.replace(&c["replace"], content);
-                }
-            }
-            x.into()
-        })
-        .collect()
+#[test]
+fn test_compare_versions() {
+    assert!(compare_versions("1.10.2", "1.9").is_gt());
+    assert!(compare_versions("33.0.0-jre", "32.1.2-jre").is_gt());
+    assert!(compare_versions("9.5", "9.5.1").is_lt());
+    assert!(compare_versions("2.1", "2.1").is_eq());
 }
 
-fn replace_arguments_from_jvm(
-    args: Vec<String>,
-    config: &RuntimeConfig,
-    version_api: &Version,
-) -> anyhow::Result<Vec<String>> {
-    let natives_dir = Path::new(&config.game_dir)
-        .join("natives")
+/// return the libraries once per `group:artifact[:classifier]`, the highest
+/// version is kept in the place of the first one, loader profiles merged
+/// into the version json often list a library again at another version
+fn dedup_libraries<'a>(libraries: impl Iterator<Item = &'a Library>) -> Vec<&'a Library> {
+    let split = |name: &'a str| {
+        let mut parts: Vec<_> = name.split(':').collect();
+        let version = if parts.len() > 2 { parts.remove(2) } else { "" };
+        (parts.join(":"), version)
+    };
+    let mut kept: Vec<&Library> = vec![];
+    let mut index = HashMap::new();
+    let mut dropped = vec![];
+    for library in libraries {
+        let (key, version) = split(&library.name);
+        let Some(&i) = index.get(&key) else {
+            index.insert(key, kept.len());
+            kept.push(library);
+            continue;
+        };
+        let (_, kept_version) = split(&kept[i].name);
+        if compare_versions(version, kept_version).is_gt() {
+            dropped.push(format!("{} (kept {})", kept[i].name, library.name));
+            kept[i] = library;
+        } else if version != kept_version {
+            dropped.push(format!("{} (kept {})", library.name, kept[i].name));
+        }
+    }
+    if !dropped.is_empty() {
+        log::warn!(
+            "dropped duplicate libraries from the classpath: {}",
+            dropped.join(", ")
+        );
+    }
+    kept
+}
+
+/// build the launch classpath: every library allowed on current os, then
+/// the client jar, joined with the platform separator
+pub fn build_classpath(config: &RuntimeConfig, version_json: &Version) -> String {
+    let libraries = version_json
+        .libraries
+        .iter()
+        .filter(|x| x.is_target_lib() && x.artifact().is_some());
+    let libraries_dir = config.libraries_dir();
+    let mut paths: Vec<String> = dedup_libraries(libraries)
+        .into_iter()
+        .filter_map(|x| x.artifact())
+        .map(|x| libraries_dir.join(&x.path).to_string_lossy().into())
+        .collect();
+
+    let client_path = config
+        .versions_dir()
+        .join(&config.game_version)
+        .join(config.game_version.clone() + ".jar")
         .to_string_lossy()
         .into();
-    let valuemap = HashMap::from([
-        ("${natives_directory}", natives_dir),
-        ("${launcher_name}", "my_launcher".into()),
-        ("${launcher_version}", "114.514".into()),
-        ("${classpath}", config.get_classpaths(version_api)?),
+    paths.push(client_path);
+    paths.join(CLASSPATH_SEPARATOR)
+}
+
+#[cfg(test)]
+fn test_version() -> Version {
+    let libraries = serde_json::json!([
+        {"name": "a:a:1", "downloads": {"artifact": {"path": "a/a.jar", "url": ""}}},
+        {
+            "name": "b:b:1",
+            "downloads": {"artifact": {"path": "b/b.jar", "url": ""}},
+            "rules": [{"action": "disallow"}]
+        },
+        {"name": "c:c:1"}
     ]);
-    Ok(replace_arguments(args, valuemap))
+    crate::api::official::test_version("1.20.4", libraries)
 }
 
-fn replace_arguments_from_game(
+#[test]
+fn test_build_classpath() {
+    let version = test_version();
+    let config = RuntimeConfig {
+        game_dir: "game".into(),
+        game_version: "1.20.4".into(),
+        ..Default::default()
+    };
+    let game_dir = std::path::Path::new("game");
+    let answer = [
+        game_dir.join("libraries").join("a/a.jar"),
+        game_dir.join("versions").join("1.20.4").join("1.20.4.jar"),
+    ]
+    .map(|x| x.to_string_lossy().into_owned())
+    .join(CLASSPATH_SEPARATOR);
+    assert_eq!(build_classpath(&config, &version), answer);
+}
+
+#[test]
+fn test_dedup_libraries() {
+    let library = |name: &str| -> Library {
+        serde_json::from_value(serde_json::json!({"name": name})).unwrap()
+    };
+    let libraries = [
+        library("com.google.guava:guava:21.0"),
+        library("org.ow2.asm:asm:9.5"),
+        library("com.google.guava:guava:32.1.2-jre"),
+        library("org.lwjgl:lwjgl:3.3.1"),
+        library("org.lwjgl:lwjgl:3.3.1:natives-linux"),
+        library("org.ow2.asm:asm:9.3"),
+        library("org.ow2.asm:asm:9.5"),
+    ];
+    let names: Vec<_> = dedup_libraries(libraries.iter())
+        .iter()
+        .map(|x| x.name.as_str())
+        .collect();
+    assert_eq!(
+        names,
+        [
+            "com.google.guava:guava:32.1.2-jre",
+            "org.ow2.asm:asm:9.5",
+            "org.lwjgl:lwjgl:3.3.1",
+            "org.lwjgl:lwjgl:3.3.1:natives-linux"
+        ]
+    );
+}
+
+/// replace every `${name}` placeholder in `args` with its value in
+/// `valuemap`, unknown placeholders are kept
+fn replace_arguments(
     args: Vec<String>,
-    config: &RuntimeConfig,
+    valuemap: &HashMap<String, String>,
 ) -> anyhow::Result<Vec<String>> {
-    let js = config.version_api()?;
-    let assets_root: String = Path::new(&config.game_dir)
-        .join("assets")
-        .to_string_lossy()
-        .into();
-    let assets_index_name = js.assets;
-    let valuemap = HashMap::from([
-        ("${auth_player_name}", config.user_name.clone()),
-        ("${version_name}", config.game_version.clone()),
-        ("${game_directory}", config.game_dir.clone()),
-        ("${assets_root}", assets_root),
-        ("${assets_index_name}", assets_index_name),
-        ("${auth_uuid}", config.user_uuid.clone()),
-        ("${user_type}", config.user_type.clone()),
-        ("${version_type}", "release".into()),
-    ]);
-    //TODO get user info
-    // valuemap.insert("${auth_access_token}","");
+    let regex = Regex::new(r"\$\{(?<name>\w+)\}")?;
+    Ok(args
+        .iter()
+        .map(|x| {
+            regex
+                .replace_all(x, |c: &regex::Captures| {
+                    valuemap
+                        .get(&c["name"])
+                        .cloned()
+                        .unwrap_or_else(|| c[0].to_owned())
+                })
+                .into_owned()
+        })
+        .collect())
+}
 
-    Ok(replace_arguments(args, valuemap))
+/// return the value of every launch placeholder, keyed by the name inside
+/// `${}`
+pub fn build_substitutions(
+    config: &RuntimeConfig,
+    version_json: &Version,
+) -> HashMap<String, String> {
+    let path = |x: PathBuf| x.to_string_lossy().into_owned();
+    let natives_dir = config
+        .versions_dir()
+        .join(&config.game_version)
+        .join("natives");
+    let assets_root = config.assets_dir();
+    // versions before 1.7 read assets by their original path from game_assets
+    let game_assets = config
+        .legacy_assets_dir(&version_json.asset_index.id)
+        .unwrap_or_else(|| assets_root.clone());
+    // offline accounts have no token, but the game still expects a value
+    let access_token = if config.access_token.is_empty() {
+        "0".to_owned()
+    } else {
+        config.access_token.clone()
+    };
+    let uuid = if config.user_type == "offline" {
+        offline_uuid(&config.user_name)
+    } else {
+        config.user_uuid.clone()
+    };
+    // versions before 1.7.3 take the session as one token
+    let session = format!("token:{access_token}:{}", uuid.replace('-', ""));
+    HashMap::from([
+        ("natives_directory".into(), path(natives_dir)),
+        ("launcher_name".into(), "my_launcher".into()),
+        ("launcher_version".into(), "114.514".into()),
+        ("classpath".into(), build_classpath(config, version_json)),
+        // the module path of neoforge and forge 1.17 and later
+        ("classpath_separator".into(), CLASSPATH_SEPARATOR.into()),
+        ("library_directory".into(), path(config.libraries_dir())),
+        ("auth_player_name".into(), config.user_name.clone()),
+        ("version_name".into(), config.game_version.clone()),
+        ("game_directory".into(), path(config.game_dir.clone())),
+        ("assets_root".into(), path(assets_root)),
+        ("game_assets".into(), path(game_assets)),
+        ("assets_index_name".into(), version_json.assets.clone()),
+        ("auth_uuid".into(), uuid),
+        ("auth_access_token".into(), access_token),
+        ("auth_session".into(), session),
+        // twitch properties of 1.7, which offline and msa accounts do not have
+        ("user_properties".into(), "{}".into()),
+        ("user_type".into(), config.user_type.clone()),
+        ("version_type".into(), version_json.r#type.clone()),
+    ])
 }
 
 impl RuntimeConfig {
     pub fn args_provider(&self) -> anyhow::Result<Vec<String>> {
-        let mut args = vec![
-            format!("-Xmx{}m", self.max_memory_size),
+        let mut args = self.authlib_injector_args()?;
+        args.append(&mut self.memory_args()?);
+        args.append(&mut vec![
             format!("-Xmn256m"),
-            format!("-XX:+UseG1GC"),
             format!("-XX:-UseAdaptiveSizePolicy"),
             format!("-XX:-OmitStackTraceInFastThrow"),
             format!("-Dfml.ignoreInvalidMinecraftCertificates=True"),
             format!("-Dfml.ignorePatchDiscrepancies=True"),
             format!("-Dlog4j2.formatMsgNoLookups=true"),
             format!("-XX:HeapDumpPath=MojangTricksIntelDriversForPerformance_javaw.exe_minecraft.exe.heapdump"),
-        ];
+        ]);
+        // the jvm refuses to start with two garbage collectors
+        let custom_gc = self
+            .jvm_args
+            .iter()
+            .any(|x| x.starts_with("-XX:+Use") && x.ends_with("GC"));
+        if !custom_gc {
+            args.push("-XX:+UseG1GC".into());
+        }
+        args.append(&mut self.jvm_args.clone());
 
         let js = self.version_api()?;
-        let jvm = &mut js.arguments.jvm.clone();
+        let (jvm, game) = &mut match &js.arguments {
+            Some(arguments) => (arguments.jvm.clone(), arguments.game.clone()),
+            // versions before 1.13 only provide the game arguments
+            None => (
+                vec![
+                    "-Djava.library.path=${natives_directory}".into(),
+                    "-cp".into(),
+                    "${classpath}".into(),
+                ],
+                js.minecraft_arguments
+                    .as_deref()
+                    .unwrap_or_default()
+                    .split_whitespace()
+                    .map(|x| x.into())
+                    .collect(),
+            ),
+        };
 
+        let substitutions = build_substitutions(self, &js);
         let jvm_args = self.get_normal_args_from(jvm)?;
-        let mut jvm_args = replace_arguments_from_jvm(jvm_args, self, &js)?;
-        args.append(&mut jvm_args);
-        args.push(js.main_class.as_str().into());
+        args.append(&mut replace_arguments(jvm_args, &substitutions)?);
+        args.push(self.main_class.clone().unwrap_or(js.main_class));
 
-        let game = &mut js.arguments.game.clone();
         let game_args = self.get_normal_args_from(game)?;
-        let mut game_args = replace_arguments_from_game(game_args, self)?;
-        args.append(&mut game_args);
+        args.append(&mut replace_arguments(game_args, &substitutions)?);
+        args.append(&mut self.window_args()?);
 
         Ok(args)
     }
 
-    fn get_normal_args_from(&self, js: &mut Vec<serde_json::Value>) -> anyhow::Result<Vec<String>> {
-        Ok(js
-            .iter()
-            .filter(|x| x.is_string())
-            .map(|x| x.as_str().unwrap().into())
-            .collect())
+    /// return `-Xms` and `-Xmx` from the memory sizes
+    /// # Error
+    /// Return Error if the min memory size is zero or larger than the max
+    fn memory_args(&self) -> anyhow::Result<Vec<String>> {
+        if self.min_memory_size == 0 || self.min_memory_size > self.max_memory_size {
+            anyhow::bail!(
+                "min_memory_size {} must be positive and at most max_memory_size {}",
+                self.min_memory_size,
+                self.max_memory_size
+            );
+        }
+        if self.max_memory_size < 512 {
+            log::warn!(
+                "max_memory_size {}m is too small to run the game",
+                self.max_memory_size
+            );
+        }
+        Ok(vec![
+            format!("-Xms{}m", self.min_memory_size),
+            format!("-Xmx{}m", self.max_memory_size),
+        ])
     }
 
-    fn get_classpaths(&self, version_api: &Version) -> anyhow::Result<String> {
-        let mut paths: Vec<String> = version_api
-            .libraries
-            .iter()
-            .filter(|x| x.is_target_lib())
-            .map(|x| {
-                Path::new(&self.game_dir)
-                    .join("libraries")
-                    .join(&x.downloads.artifact.path)
-                    .to_string_lossy()
-                    .into()
-            })
-            .collect();
-
-        let client_path = Path::new(&self.game_dir)
-            .join("versions")
-            .join(&self.game_version)
-            .join(self.game_version.clone() + ".jar")
-            .to_string_lossy()
-            .into();
-        paths.push(client_path);
-        Ok(paths.join(CLASSPATH_SEPARATOR))
+    /// return `-javaagent:<jar>=<auth_server>` if `authlib_injector` is set,
+    /// the jar path is made absolute as the game runs in game_dir
+    /// # Error
+    /// Return Error if the jar does not exist
+    fn authlib_injector_args(&self) -> anyhow::Result<Vec<String>> {
+        let (Some(jar), Some(url)) = (&self.authlib_injector, &self.auth_server) else {
+            return Ok(vec![]);
+        };
+        let jar = std::path::absolute(self.game_dir.join(jar))?;
+        if !jar.is_file() {
+            anyhow::bail!("authlib_injector {} is not a file", jar.display());
+        }
+        Ok(vec![format!("-javaagent:{}={url}", jar.display())])
     }
 
-    fn version_api(&self) -> anyhow::Result<Version> {
-        let jsfile_path = Path::new(&self.game_dir)
-            .join("versions")
+    /// return `--width` and `--height` from the window size
+    /// # Error
+    /// Return Error if the width or height is zero
+    fn window_args(&self) -> anyhow::Result<Vec<String>> {
+        if self.window_weight == 0 || self.window_height == 0 {
+            anyhow::bail!(
+                "window size {}x{} must be positive",
+                self.window_weight,
+                self.window_height
+            );
+        }
+        if self.window_weight > 7680 || self.window_height > 4320 {
+            log::warn!(
+                "window size {}x{} is larger than most monitors",
+                self.window_weight,
+                self.window_height
+            );
+        }
+        Ok(vec![
+            "--width".into(),
+            self.window_weight.to_string(),
+            "--height".into(),
+            self.window_height.to_string(),
+        ])
+    }
+
+    /// return the arguments of a version json argument list: plain strings,
+    /// and the `value` of conditional entries whose rules match
+    fn get_normal_args_from(&self, js: &mut [serde_json::Value]) -> anyhow::Result<Vec<String>> {
+        let mut args = vec![];
+        for arg in js.iter() {
+            if let Some(arg) = arg.as_str() {
+                args.push(arg.into());
+                continue;
+            }
+            let rules: Vec<Rules> = serde_json::from_value(arg["rules"].clone())?;
+            if !rules_match(&rules) {
+                continue;
+            }
+            match &arg["value"] {
+                serde_json::Value::String(value) => args.push(value.clone()),
+                serde_json::Value::Array(values) => {
+                    args.extend(values.iter().filter_map(|x| x.as_str()).map(|x| x.into()))
+                }
+                _ => {}
+            }
+        }
+        Ok(args)
+    }
+
+    /// read the installed version json of `game_version`
+    pub fn version_api(&self) -> anyhow::Result<Version> {
+        let jsfile_path = self
+            .versions_dir()
             .join(&self.game_version)
             .join(self.game_version.clone() + ".json");
         let jsfile = fs::read_to_string(jsfile_path)?;
@@ -142,25 +388,137 @@ impl RuntimeConfig {
     }
 }
 
+#[test]
+fn test_build_substitutions() {
+    let config = RuntimeConfig {
+        game_version: "1.20.4".into(),
+        ..Default::default()
+    };
+    let substitutions = build_substitutions(&config, &test_version());
+    for name in [
+        "auth_player_name",
+        "auth_uuid",
+        "auth_access_token",
+        "version_name",
+        "game_directory",
+        "assets_root",
+        "assets_index_name",
+        "user_type",
+        "version_type",
+        "natives_directory",
+        "classpath",
+        "classpath_separator",
+        "library_directory",
+    ] {
+        assert!(!substitutions[name].is_empty(), "{name} is empty");
+    }
+}
+
+#[test]
+fn test_legacy_arguments() {
+    let config = RuntimeConfig {
+        game_version: "1.7.2".into(),
+        user_name: "Notch".into(),
+        ..Default::default()
+    };
+    let mut version = test_version();
+    version.asset_index.id = "legacy".into();
+    let template = "--username ${auth_player_name} --session ${auth_session} \
+                    --version ${version_name} --gameDir ${game_directory} \
+                    --assetsDir ${game_assets} --uuid ${auth_uuid} \
+                    --accessToken ${auth_access_token} --userProperties ${user_properties}";
+    let args = template.split_whitespace().map(|x| x.into()).collect();
+    let args = replace_arguments(args, &build_substitutions(&config, &version)).unwrap();
+    assert!(args.iter().all(|x| !x.contains("${")), "{args:?}");
+    assert_eq!(args[3], "token:0:b50ad385829d3141a2167e7d7539ba7f");
+    assert!(args[9].ends_with("legacy"), "{}", args[9]);
+    assert_eq!(args[15], "{}");
+}
+
+#[test]
+fn test_conditional_arguments() {
+    let mut args = serde_json::json!([
+        "--username",
+        {"rules": [{"action": "allow"}], "value": ["-Xss1M", "-Dmark=1"]},
+        {"rules": [{"action": "allow", "features": {"is_demo_user": true}}], "value": "--demo"},
+        {"rules": [{"action": "disallow"}], "value": "--never"}
+    ]);
+    let args = RuntimeConfig::default()
+        .get_normal_args_from(args.as_array_mut().unwrap())
+        .unwrap();
+    assert_eq!(args, ["--username", "-Xss1M", "-Dmark=1"]);
+}
+
+#[test]
+fn test_memory_args() {
+    let mut config = RuntimeConfig {
+        min_memory_size: 1024,
+        max_memory_size: 4096,
+        ..Default::default()
+    };
+    assert_eq!(config.memory_args().unwrap(), ["-Xms1024m", "-Xmx4096m"]);
+    config.min_memory_size = 8192;
+    assert!(config.memory_args().is_err());
+}
+
+#[test]
+fn test_window_args() {
+    let mut config = RuntimeConfig {
+        window_weight: 1920,
+        window_height: 1080,
+        ..Default::default()
+    };
+    assert_eq!(
+        config.window_args().unwrap(),
+        ["--width", "1920", "--height", "1080"]
+    );
+    config.window_height = 0;
+    assert!(config.window_args().is_err());
+}
+
+#[test]
+fn test_authlib_injector_args() {
+    let game_dir = crate::TestDir::new("authlib_injector");
+    let mut config = RuntimeConfig {
+        game_dir: game_dir.to_path_buf(),
+        ..Default::default()
+    };
+    assert!(config.authlib_injector_args().unwrap().is_empty());
+    config.authlib_injector = Some("authlib-injector.jar".into());
+    config.auth_server = Some("https://littleskin.cn/api/yggdrasil".into());
+    assert!(config.authlib_injector_args().is_err());
+    let jar = game_dir.join("authlib-injector.jar");
+    fs::write(&jar, "").unwrap();
+    assert_eq!(
+        config.authlib_injector_args().unwrap(),
+        [format!(
+            "-javaagent:{}=https://littleskin.cn/api/yggdrasil",
+            jar.display()
+        )]
+    );
+}
+
 #[test]
 fn test_replace_arguments() {
     let valuemap = HashMap::from([
-        ("${natives_directory}", "native".into()),
-        ("${launcher_name}", "launcher".into()),
+        ("natives_directory".into(), "native".into()),
+        ("launcher_name".into(), "launcher".into()),
     ]);
     let args = Vec::from([
         "start--${natives_directory}--end".into(),
         "${abababa}end".into(),
         "normal".into(),
+        "${launcher_name}-${natives_directory}".into(),
     ]);
 
     let answer = Vec::from([
         "start--native--end".to_string(),
         "${abababa}end".to_string(),
         "normal".to_string(),
+        "launcher-native".to_string(),
     ]);
 
-    let res = replace_arguments(args, valuemap);
+    let res = replace_arguments(args, &valuemap).unwrap();
 
     assert_eq!(answer, res);
 }
@@ -1,16 +1,362 @@
+use crate::api::official::Version;
 use crate::config::RuntimeConfig;
-use std::io;
+use crate::java::check_java;
+use chrono::{DateTime, Local};
+use std::collections::VecDeque;
+use std::fs;
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::path::Path;
 use std::process::{Command, Stdio};
+use std::sync::{Arc, Mutex};
+use std::thread;
 
-pub fn gameruntime(config: RuntimeConfig) -> anyhow::Result<()> {
-    let args = config.args_provider()?;
-    let path = config.java_path;
-    let mut child = Command::new(path)
+/// options of one launch, set from the command line
+#[derive(Debug, Default, Clone)]
+pub struct LaunchOptions {
+    /// server to join, as `host[:port]`
+    pub server: Option<String>,
+    /// singleplayer world to open
+    pub world: Option<String>,
+    /// launch the demo, for accounts without a license
+    pub demo: bool,
+    /// start in fullscreen
+    pub fullscreen: bool,
+}
+
+/// split a server address into host and port, the port defaults to 25565
+fn parse_server(addr: &str) -> anyhow::Result<(String, u16)> {
+    let (host, port) = match addr.rsplit_once(':') {
+        Some((host, port)) => {
+            let port = port
+                .parse()
+                .map_err(|_| anyhow::anyhow!("invalid port in server address {addr}"))?;
+            (host, port)
+        }
+        None => (addr, 25565),
+    };
+    if host.is_empty() || host.contains(char::is_whitespace) || port == 0 {
+        anyhow::bail!("invalid server address {addr}, expect host[:port]");
+    }
+    Ok((host.to_owned(), port))
+}
+
+#[test]
+fn test_parse_server() {
+    assert_eq!(
+        parse_server("mc.example.com").unwrap(),
+        ("mc.example.com".into(), 25565)
+    );
+    assert_eq!(
+        parse_server("127.0.0.1:25566").unwrap(),
+        ("127.0.0.1".into(), 25566)
+    );
+    assert!(parse_server("127.0.0.1:port").is_err());
+    assert!(parse_server(":25565").is_err());
+}
+
+impl LaunchOptions {
+    /// return the game arguments of the options, versions before quick play
+    /// join a server with `--server` and `--port`
+    fn game_args(&self, version: &Version) -> anyhow::Result<Vec<String>> {
+        let mut args = vec![];
+        let quick_play = version.supports_quick_play();
+        if let Some(addr) = &self.server {
+            let (host, port) = parse_server(addr)?;
+            if quick_play {
+                args.push("--quickPlayMultiplayer".into());
+                args.push(format!("{host}:{port}"));
+            } else {
+                args.extend(["--server".into(), host, "--port".into(), port.to_string()]);
+            }
+        }
+        if let Some(world) = &self.world {
+            if !quick_play {
+                anyhow::bail!(
+                    "{} can not open a world directly, quick play needs 1.20 or newer",
+                    version.id
+                );
+            }
+            args.push("--quickPlaySingleplayer".into());
+            args.push(world.clone());
+        }
+        if self.demo {
+            args.push("--demo".into());
+        }
+        if self.fullscreen {
+            args.push("--fullscreen".into());
+        }
+        Ok(args)
+    }
+}
+
+/// move `logs/latest-launch.log` to `logs/<time it was written>.log`, with
+/// `-<n>` appended if a launch in the same second took that name, and return
+/// the new `logs/latest-launch.log`
+fn rotate_launch_log(logs_dir: &Path) -> anyhow::Result<fs::File> {
+    fs::create_dir_all(logs_dir)?;
+    let latest = logs_dir.join("latest-launch.log");
+    if let Ok(modified) = fs::metadata(&latest).and_then(|x| x.modified()) {
+        let time: DateTime<Local> = modified.into();
+        let stem = time.format("%Y-%m-%d_%H-%M-%S").to_string();
+        let mut rotated = logs_dir.join(format!("{stem}.log"));
+        let mut n = 1;
+        while rotated.exists() {
+            rotated = logs_dir.join(format!("{stem}-{n}.log"));
+            n += 1;
+        }
+        fs::rename(&latest, rotated)?;
+    }
+    Ok(fs::File::create(latest)?)
+}
+
+#[test]
+fn test_rotate_launch_log() {
+    let dir = crate::TestDir::new("rotate_launch_log");
+    let logs_dir = dir.join("logs");
+    write!(rotate_launch_log(&logs_dir).unwrap(), "first launch").unwrap();
+    rotate_launch_log(&logs_dir).unwrap();
+    let logs: Vec<_> = fs::read_dir(&logs_dir).unwrap().flatten().collect();
+    assert_eq!(logs.len(), 2);
+    let rotated = logs
+        .iter()
+        .find(|x| x.file_name() != "latest-launch.log")
+        .unwrap();
+    assert_eq!(fs::read_to_string(rotated.path()).unwrap(), "first launch");
+
+    // a second launch in the same second keeps the first rotated log
+    let mut latest = rotate_launch_log(&logs_dir).unwrap();
+    write!(latest, "second launch").unwrap();
+    latest
+        .set_modified(fs::metadata(rotated.path()).unwrap().modified().unwrap())
+        .unwrap();
+    drop(latest);
+    rotate_launch_log(&logs_dir).unwrap();
+    let mut texts: Vec<_> = fs::read_dir(&logs_dir)
+        .unwrap()
+        .flatten()
+        .map(|x| fs::read_to_string(x.path()).unwrap())
+        .collect();
+    texts.sort();
+    assert_eq!(texts, ["", "", "first launch", "second launch"]);
+}
+
+/// number of output lines shown when the game crashes
+const CRASH_LINES: usize = 20;
+
+/// copy everything from `reader` to both `console` and `log`, and return the
+/// last `CRASH_LINES` lines
+fn tee<R, W>(
+    reader: R,
+    mut console: W,
+    log: Arc<Mutex<fs::File>>,
+) -> thread::JoinHandle<VecDeque<String>>
+where
+    R: Read + Send + 'static,
+    W: Write + Send + 'static,
+{
+    thread::spawn(move || {
+        let mut tail = VecDeque::new();
+        let mut reader = BufReader::new(reader);
+        let mut line = vec![];
+        while let Ok(n) = reader.read_until(b'\n', &mut line) {
+            if n == 0 {
+                break;
+            }
+            let _ = console.write_all(&line);
+            let _ = log.lock().unwrap().write_all(&line);
+            if tail.len() == CRASH_LINES {
+                tail.pop_front();
+            }
+            tail.push_back(String::from_utf8_lossy(&line).trim_end().to_owned());
+            line.clear();
+        }
+        tail
+    })
+}
+
+#[test]
+fn test_tee_keeps_tail() {
+    let dir = crate::TestDir::new("tee_keeps_tail");
+    let file = dir.join("latest-launch.log");
+    let log = Arc::new(Mutex::new(fs::File::create(&file).unwrap()));
+    let output: String = (0..25).map(|x| format!("line {x}\n")).collect();
+    let tail = tee(io::Cursor::new(output.clone()), io::sink(), log)
+        .join()
+        .unwrap();
+    assert_eq!(tail.len(), CRASH_LINES);
+    assert_eq!(tail.back().unwrap(), "line 24");
+    assert_eq!(fs::read_to_string(&file).unwrap(), output);
+}
+
+/// return the java command running `args` in `config.game_dir` with the
+/// environment variables of the config
+fn game_command(config: &RuntimeConfig, args: Vec<String>) -> Command {
+    let mut command = Command::new(&config.java_path);
+    command
         .args(args)
+        .envs(&config.env)
+        .current_dir(&config.game_dir);
+    command
+}
+
+#[test]
+fn test_game_command() {
+    let config = RuntimeConfig {
+        game_dir: "/games/minecraft".into(),
+        env: [("MESA_GL_VERSION_OVERRIDE".into(), "4.5".into())].into(),
+        ..Default::default()
+    };
+    let command = game_command(&config, vec!["-Xmx2048m".into()]);
+    assert_eq!(command.get_program(), "java");
+    assert_eq!(command.get_args().collect::<Vec<_>>(), ["-Xmx2048m"]);
+    assert_eq!(
+        command.get_current_dir(),
+        Some(Path::new("/games/minecraft"))
+    );
+    let env: Vec<_> = command.get_envs().collect();
+    assert_eq!(
+        env,
+        [("MESA_GL_VERSION_OVERRIDE".as_ref(), Some("4.5".as_ref()))]
+    );
+}
+
+/// return the java command which launches `config.game_version` with the
+/// `[versions.<id>]` override applied, nothing is started
+/// # Error
+/// Return Error if the version is not installed or the java is too old
+pub fn launch_command(config: &RuntimeConfig, options: &LaunchOptions) -> anyhow::Result<Command> {
+    let mut config = config.clone().with_version_override();
+    if !config.game_dir.is_dir() {
+        anyhow::bail!(
+            "game_dir {} does not exist, install a version first",
+            config.game_dir.display()
+        );
+    }
+    // the game runs in game_dir, a relative `--gameDir` or classpath would
+    // be resolved against it a second time
+    config.game_dir = std::path::absolute(&config.game_dir)?;
+    let version = config.version_api()?;
+    check_java(Path::new(&config.java_path), &version)?;
+    let mut args = config.args_provider()?;
+    args.append(&mut options.game_args(&version)?);
+    Ok(game_command(&config, args))
+}
+
+/// run the hook command `hook` of the config named `name` in game_dir, its
+/// output is copied to `stdout` and `stderr` and written into `log`, an
+/// empty hook does nothing
+/// # Error
+/// Return Error if the command can not be started or fails
+fn run_hook<O, E>(
+    name: &str,
+    hook: &[String],
+    config: &RuntimeConfig,
+    log: &Arc<Mutex<fs::File>>,
+    (stdout, stderr): (O, E),
+) -> anyhow::Result<()>
+where
+    O: Write + Send + 'static,
+    E: Write + Send + 'static,
+{
+    let Some((program, args)) = hook.split_first() else {
+        return Ok(());
+    };
+    writeln!(log.lock().unwrap(), "---- {name}: {} ----", hook.join(" "))?;
+    let mut child = Command::new(program)
+        .args(args)
+        .envs(&config.env)
+        .current_dir(&config.game_dir)
         .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| anyhow::anyhow!("can not run {name} {program}: {e}"))?;
+    let stdout = tee(child.stdout.take().unwrap(), stdout, log.clone());
+    let stderr = tee(child.stderr.take().unwrap(), stderr, log.clone());
+    let status = child.wait()?;
+    stdout.join().unwrap();
+    stderr.join().unwrap();
+    if !status.success() {
+        anyhow::bail!("{name} {program} failed with {status}");
+    }
+    Ok(())
+}
+
+#[cfg(unix)]
+#[test]
+fn test_run_hook() {
+    let dir = crate::TestDir::new("run_hook");
+    let config = RuntimeConfig {
+        game_dir: dir.to_path_buf(),
+        env: [("HOOK".into(), "mounted".into())].into(),
+        ..Default::default()
+    };
+    let file = dir.join("latest-launch.log");
+    let log = Arc::new(Mutex::new(fs::File::create(&file).unwrap()));
+    let hook = ["sh".into(), "-c".into(), "echo $HOOK".into()];
+    let quiet = || (io::sink(), io::sink());
+    run_hook("pre_launch", &hook, &config, &log, quiet()).unwrap();
+    assert!(run_hook("pre_launch", &[], &config, &log, quiet()).is_ok());
+    assert!(run_hook("post_launch", &["false".into()], &config, &log, quiet()).is_err());
+    assert_eq!(
+        fs::read_to_string(&file).unwrap(),
+        "---- pre_launch: sh -c echo $HOOK ----\nmounted\n---- post_launch: false ----\n"
+    );
+}
+
+/// launch the game between the pre_launch and post_launch hooks, print its
+/// output and keep it in `logs/latest-launch.log`
+/// # Error
+/// Return Error with the last lines of the output if the game crashed, or
+/// if the pre_launch hook failed
+pub fn gameruntime(config: RuntimeConfig, options: &LaunchOptions) -> anyhow::Result<()> {
+    let mut command = launch_command(&config, options)?;
+    let logs_dir = config.game_dir.join("logs");
+    let log_path = logs_dir.join("latest-launch.log");
+    let log = Arc::new(Mutex::new(rotate_launch_log(&logs_dir)?));
+    let console = || (io::stdout(), io::stderr());
+    run_hook("pre_launch", &config.pre_launch, &config, &log, console())?;
+    let res = run_game(&mut command, log.clone(), &log_path);
+    // the game already ran, a failed post_launch does not fail the launch
+    if let Err(e) = run_hook("post_launch", &config.post_launch, &config, &log, console()) {
+        log::warn!("{e}");
+    }
+    res
+}
+
+/// spawn the game of `command` with its output teed into `log`
+fn run_game(
+    command: &mut Command,
+    log: Arc<Mutex<fs::File>>,
+    log_path: &Path,
+) -> anyhow::Result<()> {
+    let mut child = command
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
         .spawn()?;
 
-    io::copy(&mut child.stdout.take().unwrap(), &mut io::stdout())?;
-    child.wait()?;
-    Ok(())
+    let stdout = tee(child.stdout.take().unwrap(), io::stdout(), log.clone());
+    let stderr = tee(child.stderr.take().unwrap(), io::stderr(), log);
+    let status = child.wait()?;
+    let stdout_tail = stdout.join().unwrap();
+    let stderr_tail = stderr.join().unwrap();
+    if status.success() {
+        println!("Game exited normally");
+        return Ok(());
+    }
+
+    // the game logs most crashes to stdout, stderr is preferred if it has any
+    let tail = if stderr_tail.is_empty() {
+        stdout_tail
+    } else {
+        stderr_tail
+    };
+    eprintln!("---- last {} lines of the game output ----", tail.len());
+    for line in tail {
+        eprintln!("{line}");
+    }
+    let full_log = log_path.display();
+    match status.code() {
+        Some(code) => anyhow::bail!("game crashed with exit code {code}, full log: {full_log}"),
+        None => anyhow::bail!("game was terminated by {status}, full log: {full_log}"),
+    }
 }